@@ -0,0 +1,113 @@
+// Shared with the library's tests, without making it part of the library.
+#[path = "../src/benchmark_data.rs"]
+mod benchmark_data;
+
+use benchmark_data::{BIG_ELEMENTS, MEDIUM_ELEMENTS, SMALL_ELEMENTS};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use partition_lib::anneal::{anneal, Options};
+use partition_lib::ckk::{ckk, n_kk, old};
+use partition_lib::gcc::{find_best_partitioning, find_best_partitioning_with_table};
+use partition_lib::generate;
+use partition_lib::greedy::{complete, multifit, MULTIFIT_ITERATIONS};
+use partition_lib::large_kk;
+use partition_lib::local_search::improve;
+use partition_lib::rnp::rnp;
+use partition_lib::snp::snp;
+use partition_lib::ss::ss;
+use partition_lib::subset::{ordered_subsets, to_assignment, Up};
+use partition_lib::transposition::Table;
+
+fn bench_ckk(c: &mut Criterion) {
+    c.bench_function("ckk_old", |b| b.iter(|| old(black_box(&SMALL_ELEMENTS))));
+    c.bench_function("ckk", |b| b.iter(|| ckk(black_box(&SMALL_ELEMENTS))));
+}
+
+fn bench_gcc(c: &mut Criterion) {
+    c.bench_function("gcc", |b| {
+        b.iter(|| find_best_partitioning(black_box(&SMALL_ELEMENTS), 4))
+    });
+    // Many copies of a few values.
+    c.bench_function("gcc_duplicates", |b| {
+        b.iter(|| find_best_partitioning(black_box(&BIG_ELEMENTS[..24]), 4))
+    });
+    // At the phase transition, where the search can't stop early at a perfect partitioning.
+    let hard = generate::near_perfect(18, 3, 0);
+    c.bench_function("gcc_hard", |b| {
+        b.iter(|| find_best_partitioning(black_box(&hard), 3))
+    });
+    c.bench_function("gcc_hard_table", |b| {
+        let mut table = Table::with_memory(1 << 20);
+        b.iter(|| find_best_partitioning_with_table(black_box(&hard), 3, &mut table))
+    });
+}
+
+fn bench_greedy(c: &mut Criterion) {
+    c.bench_function("multifit", |b| {
+        b.iter(|| multifit(black_box(&BIG_ELEMENTS), 4, MULTIFIT_ITERATIONS))
+    });
+    c.bench_function("complete_greedy", |b| {
+        b.iter(|| complete(black_box(&BIG_ELEMENTS), 4, 100_000))
+    });
+}
+
+fn bench_large_n_kk(c: &mut Criterion) {
+    let elements: Vec<i64> = (0..100_000).map(|i| (i * 7919) % 1_000_003 + 1).collect();
+    c.bench_function("large_n_kk", |b| {
+        b.iter(|| large_kk::n_kk(black_box(&elements), 4))
+    });
+}
+
+fn bench_improve(c: &mut Criterion) {
+    let partitions = n_kk(&BIG_ELEMENTS, 4).partitions;
+    c.bench_function("improve", |b| {
+        b.iter(|| improve(black_box(&BIG_ELEMENTS), partitions.clone(), None))
+    });
+}
+
+fn bench_anneal(c: &mut Criterion) {
+    let initial = to_assignment(&n_kk(&BIG_ELEMENTS, 4).partitions, BIG_ELEMENTS.len());
+    let options = Options {
+        iterations: 10_000,
+        ..Options::default()
+    };
+    c.bench_function("anneal", |b| {
+        b.iter(|| anneal(black_box(&BIG_ELEMENTS), 4, initial.clone(), &options))
+    });
+}
+
+fn bench_rnp(c: &mut Criterion) {
+    c.bench_function("rnp", |b| b.iter(|| rnp(black_box(&MEDIUM_ELEMENTS))));
+}
+
+fn bench_snp(c: &mut Criterion) {
+    c.bench_function("snp", |b| b.iter(|| snp(black_box(&MEDIUM_ELEMENTS), 4)));
+}
+
+fn bench_ss(c: &mut Criterion) {
+    c.bench_function("ss", |b| b.iter(|| ss(black_box(&SMALL_ELEMENTS))));
+}
+
+fn bench_ordered_subsets(c: &mut Criterion) {
+    let mask = (1 << SMALL_ELEMENTS.len()) - 1;
+    c.bench_function("ordered_subsets", |b| {
+        b.iter(|| {
+            ordered_subsets::<i32, Up>(mask, black_box(&SMALL_ELEMENTS))
+                .fold(0, |acc, x| acc ^ x.sum)
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_ckk,
+    bench_gcc,
+    bench_greedy,
+    bench_large_n_kk,
+    bench_improve,
+    bench_anneal,
+    bench_rnp,
+    bench_snp,
+    bench_ss,
+    bench_ordered_subsets
+);
+criterion_main!(benches);
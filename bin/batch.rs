@@ -0,0 +1,198 @@
+use partition_lib::run::with_timeout;
+use partition_lib::select::{partition_using, PartitionMethod};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct BatchOpt {
+    /// Timeout for each instance, in milliseconds. Instances can override it with "timeout_ms".
+    #[structopt(long = "timeout-ms")]
+    timeout_ms: Option<u64>,
+    /// Number of instances to solve in parallel.
+    #[structopt(long = "jobs", default_value = "1")]
+    jobs: usize,
+}
+
+// Builds a method from its CLI name plus an options object such as {"node_limit": 1000},
+// by handing them to the same parser the command line uses.
+pub fn parse_method(name: &str, options: Option<&Value>) -> Result<PartitionMethod, String> {
+    let mut args = vec!["method".to_string(), name.to_string()];
+    if let Some(options) = options {
+        let options = options
+            .as_object()
+            .ok_or_else(|| "options must be an object".to_string())?;
+        for (key, value) in options {
+            args.push(format!("--{}", key.replace('_', "-")));
+            args.push(match value {
+                Value::String(value) => value.clone(),
+                other => other.to_string(),
+            });
+        }
+    }
+    PartitionMethod::from_iter_safe(args)
+        .map_err(|err| err.message.lines().next().unwrap_or_default().to_string())
+}
+
+struct Instance {
+    id: Value,
+    elements: Vec<i32>,
+    n: u8,
+    method: PartitionMethod,
+    timeout: Option<Duration>,
+}
+
+fn parse_instance(value: &Value, default_timeout: Option<u64>) -> Result<Instance, String> {
+    let elements = serde_json::from_value(value["elements"].clone())
+        .map_err(|err| format!("bad elements: {}", err))?;
+    let n = value["n"]
+        .as_u64()
+        .filter(|&n| n > 0 && n <= u64::from(u8::MAX))
+        .ok_or_else(|| "n must be an integer from 1 to 255".to_string())?;
+    let method = value["method"]
+        .as_str()
+        .ok_or_else(|| "method must be a string".to_string())?;
+    let method = parse_method(method, value.get("options"))?;
+    let timeout = value["timeout_ms"]
+        .as_u64()
+        .or(default_timeout)
+        .map(Duration::from_millis);
+    Ok(Instance {
+        id: value.get("id").cloned().unwrap_or(Value::Null),
+        elements,
+        n: n as u8,
+        method,
+        timeout,
+    })
+}
+
+fn solve(line_number: usize, line: &str, default_timeout: Option<u64>) -> Value {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(err) => return json!({ "line": line_number, "error": err.to_string() }),
+    };
+    let instance = match parse_instance(&value, default_timeout) {
+        Ok(instance) => instance,
+        Err(err) => {
+            let id = value.get("id").cloned().unwrap_or(Value::Null);
+            return json!({ "line": line_number, "id": id, "error": err });
+        }
+    };
+    let Instance {
+        id,
+        elements,
+        n,
+        method,
+        timeout,
+    } = instance;
+    let start = Instant::now();
+    let result = with_timeout(move || partition_using(method, &elements, n), timeout);
+    let elapsed = start.elapsed();
+    match result {
+        Ok(solution) => json!({
+            "line": line_number,
+            "id": id,
+            "score": solution.score,
+            "lower_bound": solution.lower_bound,
+            "gap": solution.gap(),
+            "sums": solution.sums,
+            "bins": solution.bins(),
+            "elapsed_ms": elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis()),
+        }),
+        Err(err) => json!({ "line": line_number, "id": id, "error": err }),
+    }
+}
+
+// Solves one instance per input line and writes one JSON result per line, in input order.
+// Failures are reported on their own line instead of stopping the batch.
+pub fn run<R: BufRead, W: Write>(input: R, out: &mut W, opt: &BatchOpt) -> io::Result<()> {
+    let lines: Vec<(usize, String)> = input
+        .lines()
+        .collect::<io::Result<Vec<String>>>()?
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+    let lines = Arc::new(lines);
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = channel();
+    for _ in 0..opt.jobs.max(1) {
+        let lines = Arc::clone(&lines);
+        let next = Arc::clone(&next);
+        let sender = sender.clone();
+        let timeout = opt.timeout_ms;
+        thread::spawn(move || loop {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            let (line_number, line) = match lines.get(index) {
+                Some(line) => line,
+                None => return,
+            };
+            if sender
+                .send((index, solve(*line_number, line, timeout)))
+                .is_err()
+            {
+                return;
+            }
+        });
+    }
+    drop(sender);
+    let mut pending = BTreeMap::new();
+    let mut written = 0;
+    for (index, result) in receiver {
+        pending.insert(index, result);
+        while let Some(result) = pending.remove(&written) {
+            writeln!(out, "{}", result)?;
+            written += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::{run, BatchOpt};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn unit_batch() {
+        let input = [
+            r#"{"id": "a", "elements": [5, 4, 3, 3, 3], "n": 2, "method": "gcc"}"#,
+            "",
+            r#"{"id": "b", "elements": [1, 2], "n": 0, "method": "gcc"}"#,
+            r#"{"id": "c", "elements": [1, 2, 3], "n": 3, "method": "ckk"}"#,
+            "not json",
+        ]
+        .join("\n");
+        let opt = BatchOpt {
+            timeout_ms: None,
+            jobs: 2,
+        };
+        let mut out = Vec::new();
+        run(input.as_bytes(), &mut out, &opt).unwrap();
+        let results: Vec<Value> = serde_json::Deserializer::from_slice(&out)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0]["score"], 9);
+        assert_eq!(results[0]["sums"], json!([9, 9]));
+        assert_eq!(results[1]["id"], "b");
+        assert_eq!(results[1]["line"], 3);
+        assert_eq!(results[1]["error"], "n must be an integer from 1 to 255");
+        assert_eq!(results[2]["id"], "c");
+        assert!(results[2]["error"]
+            .as_str()
+            .unwrap()
+            .contains("2 partitions"));
+        assert_eq!(results[3]["line"], 5);
+        assert!(results[3]["error"].is_string());
+    }
+}
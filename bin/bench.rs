@@ -0,0 +1,186 @@
+use crate::batch::parse_method;
+use partition_lib::generate;
+use partition_lib::run::with_timeout;
+use partition_lib::select::{partition_using, PartitionMethod};
+use partition_lib::stats;
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Family {
+    Uniform,
+    Normal,
+    // Uniform at the precision where perfect partitions become unlikely.
+    Hard,
+}
+
+impl FromStr for Family {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "uniform" => Ok(Family::Uniform),
+            "normal" => Ok(Family::Normal),
+            "hard" => Ok(Family::Hard),
+            _ => Err(format!(
+                "unknown family {:?}, expected one of uniform, normal, hard",
+                s
+            )),
+        }
+    }
+}
+
+fn parse_list<T: FromStr>(list: &str) -> Result<Vec<T>, String> {
+    list.split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|_| format!("couldn't parse {:?}", item))
+        })
+        .collect()
+}
+
+#[derive(StructOpt, Debug)]
+pub struct BenchOpt {
+    /// Instance family: uniform, normal or hard.
+    #[structopt(long = "family", default_value = "uniform")]
+    family: Family,
+    /// Comma separated instance sizes.
+    #[structopt(long = "counts", default_value = "20")]
+    counts: String,
+    /// Number of bins.
+    #[structopt(long = "n", default_value = "2")]
+    n: u8,
+    /// Precision of uniform instances, in bits.
+    #[structopt(long = "bits", default_value = "20")]
+    bits: u32,
+    /// Mean of normal instances.
+    #[structopt(long = "mean", default_value = "1000000")]
+    mean: f64,
+    /// Standard deviation of normal instances.
+    #[structopt(long = "std-dev", default_value = "100000")]
+    std_dev: f64,
+    /// Instances generated per size.
+    #[structopt(long = "instances", default_value = "10")]
+    instances: u64,
+    /// Seed of the first instance; later instances use the following seeds.
+    #[structopt(long = "seed", default_value = "0")]
+    seed: u64,
+    /// Comma separated methods to run on every instance.
+    #[structopt(long = "methods", default_value = "kk,ckk")]
+    methods: String,
+    /// Timeout for each run, in milliseconds.
+    #[structopt(long = "timeout-ms", default_value = "10000")]
+    timeout_ms: u64,
+}
+
+// Runs every method on every generated instance and writes one CSV row per run. Nodes are
+// the search tree nodes counted through partition_lib::stats, so they're 0 for methods that
+// don't search. Runs happen one at a time: with_timeout waits for a timed-out search to stop,
+// so it doesn't skew the runs after it.
+pub fn run<W: Write>(out: &mut W, opt: &BenchOpt) -> Result<(), String> {
+    let counts: Vec<usize> = parse_list(&opt.counts)?;
+    let method_names: Vec<String> = parse_list(&opt.methods)?;
+    let methods: Vec<PartitionMethod> = method_names
+        .iter()
+        .map(|name| parse_method(name, None))
+        .collect::<Result<_, _>>()?;
+    let mut writer = csv::Writer::from_writer(out);
+    let io_error = |err: csv::Error| err.to_string();
+    writer
+        .write_record([
+            "family", "count", "n", "bits", "seed", "method", "time_ms", "nodes", "score", "status",
+        ])
+        .map_err(io_error)?;
+    for &count in &counts {
+        let bits = match opt.family {
+            Family::Hard => generate::critical_bits(count, opt.n),
+            _ => opt.bits,
+        };
+        for seed in opt.seed..opt.seed + opt.instances {
+            let elements = match opt.family {
+                Family::Uniform | Family::Hard => generate::uniform(count, bits, seed),
+                Family::Normal => generate::normal(count, opt.mean, opt.std_dev, seed),
+            };
+            for (name, &method) in method_names.iter().zip(&methods) {
+                let elements = elements.clone();
+                let n = opt.n;
+                let start = Instant::now();
+                let result = with_timeout(
+                    move || {
+                        let score = partition_using(method, &elements, n).score;
+                        (score, stats::take_nodes())
+                    },
+                    Some(Duration::from_millis(opt.timeout_ms)),
+                );
+                let elapsed = start.elapsed();
+                let time_ms =
+                    elapsed.as_secs() as f64 * 1e3 + f64::from(elapsed.subsec_nanos()) / 1e6;
+                let (score, nodes, status) = match result {
+                    Ok((score, nodes)) => (score.to_string(), nodes.to_string(), "ok".to_string()),
+                    Err(err) => (String::new(), String::new(), err),
+                };
+                let bits = match opt.family {
+                    Family::Normal => String::new(),
+                    _ => bits.to_string(),
+                };
+                writer
+                    .write_record(&[
+                        format!("{:?}", opt.family).to_lowercase(),
+                        count.to_string(),
+                        opt.n.to_string(),
+                        bits,
+                        seed.to_string(),
+                        name.clone(),
+                        format!("{:.3}", time_ms),
+                        nodes,
+                        score,
+                        status,
+                    ])
+                    .map_err(io_error)?;
+                writer.flush().map_err(|err: io::Error| err.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bench::{run, BenchOpt, Family};
+
+    fn bench(methods: &str, timeout_ms: u64) -> Vec<csv::StringRecord> {
+        let opt = BenchOpt {
+            family: Family::Uniform,
+            counts: "12,40".to_string(),
+            n: 2,
+            bits: 20,
+            mean: 0.0,
+            std_dev: 0.0,
+            instances: 1,
+            seed: 0,
+            methods: methods.to_string(),
+            timeout_ms,
+        };
+        let mut out = Vec::new();
+        run(&mut out, &opt).unwrap();
+        let mut reader = csv::Reader::from_reader(out.as_slice());
+        reader.records().map(Result::unwrap).collect()
+    }
+    #[test]
+    fn unit_bench() {
+        let rows = bench("ss,gcc", 60_000);
+        assert_eq!(rows.len(), 4);
+        for row in &rows {
+            assert_eq!(&row[9], "ok");
+            assert!(row[7].parse::<u64>().unwrap() > 0);
+        }
+        assert_eq!(rows[0][8], rows[1][8]);
+        // Complete greedy runs to its million node limit on 40 elements, which takes far longer.
+        let rows = bench("cga", 1);
+        assert_eq!(&rows[1][9], "timed out after 1ms");
+        assert_eq!(&rows[1][7], "");
+    }
+}
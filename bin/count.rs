@@ -0,0 +1,86 @@
+use crate::input::{self, Columns, InputFormat};
+use partition_lib::ess::{count_perfect_partitions, count_subsets_in_range};
+use partition_lib::item::weights;
+use partition_lib::subset::Subset;
+use serde_json::json;
+use std::io::{Read, Write};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct CountOpt {
+    /// Input format: json (an array of numbers or records), jsonl or csv.
+    #[structopt(long = "input", default_value = "json")]
+    input: InputFormat,
+    /// Field or column holding each record's weight.
+    #[structopt(long = "weight-column", default_value = "weight")]
+    weight_column: String,
+    /// Also count the subsets with a sum of at least this. Defaults to 0 if --max is given.
+    #[structopt(long = "min")]
+    min: Option<i64>,
+    /// Also count the subsets with a sum less than this. Defaults to past the total.
+    #[structopt(long = "max")]
+    max: Option<i64>,
+}
+
+// Reads one instance and writes its counts as a JSON object. Sums are widened to i64 so totals
+// of many large weights don't overflow.
+pub fn run<R: Read, W: Write>(input: R, output: &mut W, opt: &CountOpt) -> Result<(), String> {
+    let columns = Columns {
+        weight: opt.weight_column.clone(),
+        label: "id".to_string(),
+    };
+    let items = input::read(input, opt.input, &columns)?;
+    let elements: Vec<i64> = weights(&items).into_iter().map(i64::from).collect();
+    if elements.len() > 64 {
+        return Err("at most 64 elements are supported".to_string());
+    }
+    if elements.iter().any(|&x| x < 0) {
+        return Err("weights must be non-negative".to_string());
+    }
+    let all = Subset::all(&elements);
+    let mut counts = json!({
+        "elements": elements.len(),
+        "total": all.sum,
+        "perfect_partitions": count_perfect_partitions(&elements),
+    });
+    if opt.min.is_some() || opt.max.is_some() {
+        let range = opt.min.unwrap_or(0)..opt.max.unwrap_or(all.sum + 1);
+        counts["subsets_in_range"] = json!(count_subsets_in_range(all.mask, &elements, range));
+    }
+    writeln!(output, "{}", counts).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::count::{run, CountOpt};
+    use crate::input::InputFormat;
+    use serde_json::{json, Value};
+
+    fn count(elements: &[i64], max: Option<i64>) -> Result<Value, String> {
+        let opt = CountOpt {
+            input: InputFormat::Json,
+            weight_column: "weight".to_string(),
+            min: None,
+            max,
+        };
+        let mut out = Vec::new();
+        run(json!(elements).to_string().as_bytes(), &mut out, &opt)?;
+        Ok(serde_json::from_slice(&out).unwrap())
+    }
+    #[test]
+    fn unit_64_elements() {
+        let mut elements = vec![0; 64];
+        elements[..4].copy_from_slice(&[1, 1, 2, 2]);
+        let counts = count(&elements, Some(2)).unwrap();
+        assert_eq!(counts["elements"], 64);
+        assert_eq!(counts["total"], 6);
+        assert_eq!(counts["perfect_partitions"], 1_u64 << 61);
+        // The empty set and either 1, with each of the zeros in or out.
+        assert_eq!(counts["subsets_in_range"], 3_u64 << 60);
+        elements.push(0);
+        assert_eq!(
+            count(&elements, None),
+            Err("at most 64 elements are supported".to_string())
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use partition_lib::item::Item;
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::io::{BufRead, BufReader, Read};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy)]
+pub enum InputFormat {
+    Json,
+    JsonLines,
+    Csv,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "json" => Ok(InputFormat::Json),
+            "jsonl" => Ok(InputFormat::JsonLines),
+            "csv" => Ok(InputFormat::Csv),
+            _ => Err(format!(
+                "unknown input format {:?}, expected one of json, jsonl, csv",
+                s
+            )),
+        }
+    }
+}
+
+// Which fields of a labeled record hold the weight and the label.
+#[derive(Debug, Clone)]
+pub struct Columns {
+    pub weight: String,
+    pub label: String,
+}
+
+pub type Items = Vec<Item<i32, Option<String>>>;
+
+fn parse_weight(value: &Value) -> Result<i32, String> {
+    value
+        .as_i64()
+        .and_then(|weight| i32::try_from(weight).ok())
+        .ok_or_else(|| format!("weight {} is not a 32 bit integer", value))
+}
+
+fn parse_label(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(label) => Some(label.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+// Either a bare number or a record such as {"id": "job-17", "weight": 8228336}.
+fn parse_value(value: &Value, columns: &Columns) -> Result<Item<i32, Option<String>>, String> {
+    match value {
+        Value::Object(record) => {
+            let weight = record
+                .get(&columns.weight)
+                .ok_or_else(|| format!("record {} has no {:?} field", value, columns.weight))?;
+            let label = record.get(&columns.label).and_then(parse_label);
+            Ok(Item::new(label, parse_weight(weight)?))
+        }
+        _ => Ok(Item::new(None, parse_weight(value)?)),
+    }
+}
+
+fn read_json<R: Read>(input: R, columns: &Columns) -> Result<Items, String> {
+    let values: Vec<Value> =
+        serde_json::from_reader(input).map_err(|err| format!("Couldn't parse input: {}", err))?;
+    values
+        .iter()
+        .map(|value| parse_value(value, columns))
+        .collect()
+}
+
+fn read_json_lines<R: Read>(input: R, columns: &Columns) -> Result<Items, String> {
+    let mut items = Vec::new();
+    for (line_number, line) in BufReader::new(input).lines().enumerate() {
+        let line = line.map_err(|err| err.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(&line)
+            .map_err(|err| format!("Couldn't parse line {}: {}", line_number + 1, err))?;
+        items.push(parse_value(&value, columns)?);
+    }
+    Ok(items)
+}
+
+fn read_csv<R: Read>(input: R, columns: &Columns) -> Result<Items, String> {
+    let mut reader = csv::Reader::from_reader(input);
+    let headers = reader.headers().map_err(|err| err.to_string())?.clone();
+    let weight_index = headers
+        .iter()
+        .position(|header| header == columns.weight)
+        .ok_or_else(|| format!("no {:?} column", columns.weight))?;
+    let label_index = headers.iter().position(|header| header == columns.label);
+    let mut items = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|err| err.to_string())?;
+        let weight = record[weight_index].trim();
+        let weight = weight
+            .parse()
+            .map_err(|_| format!("weight {:?} is not a 32 bit integer", weight))?;
+        let label = label_index.map(|index| record[index].to_string());
+        items.push(Item::new(label, weight));
+    }
+    Ok(items)
+}
+
+pub fn read<R: Read>(input: R, format: InputFormat, columns: &Columns) -> Result<Items, String> {
+    match format {
+        InputFormat::Json => read_json(input, columns),
+        InputFormat::JsonLines => read_json_lines(input, columns),
+        InputFormat::Csv => read_csv(input, columns),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::{read, Columns, InputFormat, Items};
+    use partition_lib::item::Item;
+
+    fn columns() -> Columns {
+        Columns {
+            weight: "weight".to_string(),
+            label: "id".to_string(),
+        }
+    }
+    fn parse(input: &str, format: InputFormat) -> Result<Items, String> {
+        read(input.as_bytes(), format, &columns())
+    }
+    #[test]
+    fn unit_json() {
+        assert_eq!(
+            parse("[3, 1]", InputFormat::Json),
+            Ok(vec![Item::new(None, 3), Item::new(None, 1)])
+        );
+        let items = parse(
+            r#"[{"id": "a", "weight": 3}, {"weight": 2}, {"id": 7, "weight": 1}, 4]"#,
+            InputFormat::Json,
+        )
+        .unwrap();
+        let labels: Vec<Option<&str>> = items.iter().map(|item| item.label.as_deref()).collect();
+        assert_eq!(labels, vec![Some("a"), None, Some("7"), None]);
+        assert_eq!(
+            items.iter().map(|item| item.weight).collect::<Vec<_>>(),
+            vec![3, 2, 1, 4]
+        );
+    }
+    #[test]
+    fn unit_json_errors() {
+        assert!(parse("[3, 1", InputFormat::Json)
+            .unwrap_err()
+            .starts_with("Couldn't parse input"));
+        assert_eq!(
+            parse(r#"[{"id": "a"}]"#, InputFormat::Json),
+            Err(r#"record {"id":"a"} has no "weight" field"#.to_string())
+        );
+        assert_eq!(
+            parse("[1.5]", InputFormat::Json),
+            Err("weight 1.5 is not a 32 bit integer".to_string())
+        );
+        assert_eq!(
+            parse("[4294967296]", InputFormat::Json),
+            Err("weight 4294967296 is not a 32 bit integer".to_string())
+        );
+    }
+    #[test]
+    fn unit_json_lines() {
+        let input = "{\"id\": \"a\", \"weight\": 3}\n\n5\n";
+        let items = parse(input, InputFormat::JsonLines).unwrap();
+        assert_eq!(
+            items,
+            vec![Item::new(Some("a".to_string()), 3), Item::new(None, 5)]
+        );
+        let error = parse("3\n{\"weight\": \n", InputFormat::JsonLines).unwrap_err();
+        assert!(error.starts_with("Couldn't parse line 2"), "{}", error);
+    }
+    #[test]
+    fn unit_csv() {
+        let items = parse("id,weight\na, 3\nb,2\n", InputFormat::Csv).unwrap();
+        assert_eq!(
+            items,
+            vec![
+                Item::new(Some("a".to_string()), 3),
+                Item::new(Some("b".to_string()), 2)
+            ]
+        );
+        let items = parse("weight\n3\n", InputFormat::Csv).unwrap();
+        assert_eq!(items, vec![Item::new(None, 3)]);
+        assert_eq!(
+            parse("id,size\na,3\n", InputFormat::Csv),
+            Err("no \"weight\" column".to_string())
+        );
+        assert_eq!(
+            parse("weight\nlots\n", InputFormat::Csv),
+            Err("weight \"lots\" is not a 32 bit integer".to_string())
+        );
+        assert!(parse("id,weight\na,3,extra\n", InputFormat::Csv).is_err());
+    }
+}
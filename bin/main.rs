@@ -1,33 +1,146 @@
-extern crate cpuprofiler;
-extern crate num;
-extern crate partition_lib;
-extern crate serde_json;
-extern crate structopt;
+mod batch;
+mod bench;
+mod count;
+mod input;
+mod output;
+mod profile;
+mod verify;
 
-use self::cpuprofiler::PROFILER;
+use crate::batch::BatchOpt;
+use crate::bench::BenchOpt;
+use crate::count::CountOpt;
+use crate::input::{Columns, InputFormat};
+use crate::output::OutputFormat;
+use crate::verify::VerifyOpt;
+use partition_lib::item::weights;
 use partition_lib::select;
-use std::io::stdin;
-use std::iter::Iterator;
+use partition_lib::solver::{Problem, Solver};
+use std::env;
+use std::ffi::OsString;
+use std::io::{stdin, stdout};
+use std::process::exit;
+use std::time::Instant;
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
-struct Opt {
+struct SolveOpt {
     n: u8,
+    /// Input format: json (an array of numbers or records), jsonl or csv.
+    #[structopt(long = "input", default_value = "json")]
+    input: InputFormat,
+    /// Field or column holding each record's weight.
+    #[structopt(long = "weight-column", default_value = "weight")]
+    weight_column: String,
+    /// Field or column holding each record's label.
+    #[structopt(long = "label-column", default_value = "id")]
+    label_column: String,
+    /// Output format: values, json, csv or table.
+    #[structopt(long = "format", default_value = "values")]
+    format: OutputFormat,
+    /// Write a CPU profile of the solver to this path. Needs the profiling feature.
+    #[structopt(long = "profile")]
+    profile: Option<String>,
+    /// Print how long the solver took to stderr.
+    #[structopt(long = "timing")]
+    timing: bool,
     #[structopt(subcommand)]
     method: select::PartitionMethod,
 }
 
+#[derive(StructOpt)]
+enum Command {
+    /// Partition one instance read from stdin. Also what runs when no subcommand is given.
+    #[structopt(name = "solve")]
+    Solve(SolveOpt),
+    /// Partition one JSON instance per stdin line, writing one JSON result per line.
+    #[structopt(name = "batch")]
+    Batch(BatchOpt),
+    /// Run methods on generated random instances and report timings as CSV.
+    #[structopt(name = "bench")]
+    Bench(BenchOpt),
+    /// Check claimed partitionings read from stdin and report their gaps to the lower bound.
+    #[structopt(name = "verify")]
+    Verify(VerifyOpt),
+    /// Count the perfect partitions of one instance read from stdin, without enumerating them.
+    #[structopt(name = "count")]
+    Count(CountOpt),
+}
+
+fn solve(opt: SolveOpt) {
+    let columns = Columns {
+        weight: opt.weight_column.clone(),
+        label: opt.label_column.clone(),
+    };
+    let items = input::read(stdin(), opt.input, &columns).expect("Couldn't parse input");
+    let elements = weights(&items);
+    if let Some(ref path) = opt.profile {
+        profile::start(path);
+    }
+    let start = Instant::now();
+    let result = Solver::new(opt.method).solve(&Problem::new(elements, opt.n));
+    let elapsed = start.elapsed();
+    if opt.profile.is_some() {
+        profile::stop();
+    }
+    if opt.timing {
+        eprintln!("solved in {:?}", elapsed);
+    }
+    let result = result.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+    let stdout = stdout();
+    output::write(&mut stdout.lock(), opt.format, &items, &result).expect("Couldn't write output");
+}
+
+// Names that can come first on the command line. Anything else is taken as the start of a
+// solve, so the original `partition N method` form keeps working.
+const COMMANDS: [&str; 10] = [
+    "solve",
+    "batch",
+    "bench",
+    "verify",
+    "count",
+    "help",
+    "-h",
+    "--help",
+    "-V",
+    "--version",
+];
+
+fn args() -> Vec<OsString> {
+    let mut args: Vec<OsString> = env::args_os().collect();
+    let is_command = |arg: &OsString| COMMANDS.iter().any(|&command| arg == command);
+    if args.len() > 1 && !is_command(&args[1]) {
+        args.insert(1, "solve".into());
+    }
+    args
+}
+
 fn main() {
-    let opt = Opt::from_args();
-    let elements_result: serde_json::Result<Vec<i32>> = serde_json::from_reader(stdin());
-    let elements = elements_result.expect("Couldn't parse input");
-    PROFILER.lock().unwrap().start("main.profile").unwrap();
-    let partitions = select::partition_using(opt.method, &elements, opt.n);
-    PROFILER.lock().unwrap().stop().unwrap();
-    let output: Vec<Vec<i32>> = partitions
-        .iter()
-        .map(|subset| subset.to_vec(&elements))
-        .collect();
-    let output_string = serde_json::to_string_pretty(&output).expect("Serialization failed");
-    println!("{}", output_string);
+    match Command::from_iter(args()) {
+        Command::Solve(opt) => solve(opt),
+        Command::Batch(opt) => {
+            let stdin = stdin();
+            let stdout = stdout();
+            batch::run(stdin.lock(), &mut stdout.lock(), &opt).expect("Couldn't run batch");
+        }
+        Command::Bench(opt) => {
+            let stdout = stdout();
+            bench::run(&mut stdout.lock(), &opt).expect("Couldn't run benchmark");
+        }
+        Command::Verify(opt) => {
+            let stdin = stdin();
+            let stdout = stdout();
+            let valid =
+                verify::run(stdin.lock(), &mut stdout.lock(), &opt).expect("Couldn't verify input");
+            if !valid {
+                exit(1);
+            }
+        }
+        Command::Count(opt) => {
+            let stdout = stdout();
+            count::run(stdin(), &mut stdout.lock(), &opt).expect("Couldn't count input");
+        }
+    }
 }
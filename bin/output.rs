@@ -0,0 +1,230 @@
+use crate::input::Items;
+use partition_lib::item::weights;
+use partition_lib::solution::Solution;
+use serde_json::json;
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    // The original output: a JSON array of the values in each bin.
+    Values,
+    Json,
+    Csv,
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "values" => Ok(OutputFormat::Values),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "table" => Ok(OutputFormat::Table),
+            _ => Err(format!(
+                "unknown format {:?}, expected one of values, json, csv, table",
+                s
+            )),
+        }
+    }
+}
+
+pub fn write<W: Write>(
+    out: &mut W,
+    format: OutputFormat,
+    items: &Items,
+    solution: &Solution<i32>,
+) -> io::Result<()> {
+    let bins = solution.bins();
+    let elements = weights(items);
+    let values = |bin: &[usize]| -> Vec<i32> { bin.iter().map(|&i| elements[i]).collect() };
+    let labeled = items.iter().any(|item| item.label.is_some());
+    match format {
+        OutputFormat::Values => {
+            let output: Vec<Vec<i32>> = bins.iter().map(|bin| values(bin)).collect();
+            let output_string =
+                serde_json::to_string_pretty(&output).expect("Serialization failed");
+            writeln!(out, "{}", output_string)
+        }
+        OutputFormat::Json => {
+            let bins: Vec<serde_json::Value> = bins
+                .iter()
+                .zip(&solution.sums)
+                .enumerate()
+                .map(|(bin, (indices, sum))| {
+                    let mut output = json!({
+                        "bin": bin,
+                        "sum": sum,
+                        "indices": indices,
+                        "values": values(indices),
+                    });
+                    if labeled {
+                        let labels: Vec<_> = indices.iter().map(|&i| &items[i].label).collect();
+                        output["labels"] = json!(labels);
+                    }
+                    output
+                })
+                .collect();
+            let output = json!({
+                "score": solution.score,
+                "lower_bound": solution.lower_bound,
+                "gap": solution.gap(),
+                "bins": bins,
+            });
+            let output_string =
+                serde_json::to_string_pretty(&output).expect("Serialization failed");
+            writeln!(out, "{}", output_string)
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            if labeled {
+                writer.write_record(["index", "label", "value", "bin"])?;
+            } else {
+                writer.write_record(["index", "value", "bin"])?;
+            }
+            for (i, item) in items.iter().enumerate() {
+                let bin = solution.assignment[i];
+                let mut record = vec![i.to_string()];
+                if labeled {
+                    record.push(item.label.clone().unwrap_or_default());
+                }
+                record.push(item.weight.to_string());
+                record.push(bin.to_string());
+                writer.write_record(&record)?;
+            }
+            writer.flush()
+        }
+        OutputFormat::Table => {
+            let column = if labeled { "labels" } else { "indices" };
+            writeln!(
+                out,
+                "{:>4}  {:>12}  {:>5}  {}",
+                "bin", "sum", "count", column
+            )?;
+            for (bin, (indices, sum)) in bins.iter().zip(&solution.sums).enumerate() {
+                let members: Vec<String> = indices
+                    .iter()
+                    .map(|&i| match items[i].label {
+                        Some(ref label) => label.clone(),
+                        None if labeled => format!("#{}", i),
+                        None => i.to_string(),
+                    })
+                    .collect();
+                writeln!(
+                    out,
+                    "{:>4}  {:>12}  {:>5}  {}",
+                    bin,
+                    sum,
+                    members.len(),
+                    members.join(" ")
+                )?;
+            }
+            writeln!(out, "score: {}", solution.score)?;
+            match (solution.lower_bound, solution.gap()) {
+                (Some(lower_bound), Some(gap)) => writeln!(
+                    out,
+                    "lower bound: {} (gap {:.2}%)",
+                    lower_bound,
+                    gap * 100.0
+                ),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::Items;
+    use crate::output::{write, OutputFormat};
+    use partition_lib::item::Item;
+    use partition_lib::solution::Solution;
+    use serde_json::{json, Value};
+
+    fn items(labels: [Option<&str>; 3]) -> Items {
+        labels
+            .iter()
+            .zip(&[6, 3, 2])
+            .map(|(label, &weight)| Item::new(label.map(str::to_string), weight))
+            .collect()
+    }
+    fn format(format: OutputFormat, items: &Items, bound: bool) -> String {
+        let elements = [6, 3, 2];
+        let mut solution = Solution::from_assignment(&[0, 1, 1], &elements, 2);
+        if bound {
+            solution = solution.with_bound(&elements);
+        }
+        let mut out = Vec::new();
+        write(&mut out, format, items, &solution).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+    #[test]
+    fn unit_values() {
+        let output = format(OutputFormat::Values, &items([None; 3]), true);
+        let values: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(values, json!([[6], [3, 2]]));
+    }
+    #[test]
+    fn unit_json() {
+        let output = format(OutputFormat::Json, &items([None; 3]), true);
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            output,
+            json!({
+                "score": 6,
+                "lower_bound": 6,
+                "gap": 0.0,
+                "bins": [
+                    {"bin": 0, "sum": 6, "indices": [0], "values": [6]},
+                    {"bin": 1, "sum": 5, "indices": [1, 2], "values": [3, 2]},
+                ],
+            })
+        );
+        let output = format(
+            OutputFormat::Json,
+            &items([Some("a"), None, Some("c")]),
+            false,
+        );
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["lower_bound"], Value::Null);
+        assert_eq!(output["bins"][1]["labels"], json!([null, "c"]));
+    }
+    #[test]
+    fn unit_csv() {
+        let output = format(OutputFormat::Csv, &items([None; 3]), true);
+        assert_eq!(output, "index,value,bin\n0,6,0\n1,3,1\n2,2,1\n");
+        let output = format(
+            OutputFormat::Csv,
+            &items([Some("a"), None, Some("c")]),
+            true,
+        );
+        assert_eq!(output, "index,label,value,bin\n0,a,6,0\n1,,3,1\n2,c,2,1\n");
+    }
+    #[test]
+    fn unit_table() {
+        let output = format(OutputFormat::Table, &items([None; 3]), true);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                " bin           sum  count  indices",
+                "   0             6      1  0",
+                "   1             5      2  1 2",
+                "score: 6",
+                "lower bound: 6 (gap 0.00%)",
+            ]
+        );
+        // Unlabeled items among labeled ones are shown by index.
+        let output = format(
+            OutputFormat::Table,
+            &items([Some("a"), None, Some("c")]),
+            false,
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], " bin           sum  count  labels");
+        assert_eq!(lines[2], "   1             5      2  #1 c");
+        assert_eq!(lines.len(), 4);
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(feature = "profiling")]
+use cpuprofiler::PROFILER;
+#[cfg(not(feature = "profiling"))]
+use std::process::exit;
+
+#[cfg(feature = "profiling")]
+pub fn start(path: &str) {
+    PROFILER
+        .lock()
+        .unwrap()
+        .start(path)
+        .expect("Couldn't start the profiler");
+}
+
+#[cfg(feature = "profiling")]
+pub fn stop() {
+    PROFILER
+        .lock()
+        .unwrap()
+        .stop()
+        .expect("Couldn't stop the profiler");
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn start(_path: &str) {
+    eprintln!("--profile needs a build with the profiling feature enabled");
+    exit(2);
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn stop() {}
@@ -0,0 +1,176 @@
+use partition_lib::subset::Subset;
+use partition_lib::verify::{certify, verify, Certificate};
+use serde_json::{json, Value};
+use std::io;
+use std::io::{Read, Write};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct VerifyOpt {
+    /// Bins list element values, as in the values output format, instead of indices.
+    #[structopt(long = "values")]
+    values: bool,
+    /// When the lower bound isn't met, run an exact search to check optimality.
+    #[structopt(long = "certify")]
+    certify: bool,
+}
+
+struct Claim {
+    id: Value,
+    elements: Vec<i32>,
+    n: u8,
+    partitions: Vec<Subset<i32, u64>>,
+}
+
+// Finds an element with this value that no bin has used yet, so that a values listing can be
+// turned back into masks even when values repeat.
+fn unused_index(elements: &[i32], used: u64, value: i32) -> Option<usize> {
+    (0..elements.len()).find(|&i| elements[i] == value && used & (1 << i) == 0)
+}
+
+fn parse_mask(
+    members: &Value,
+    elements: &[i32],
+    used: &mut u64,
+    values: bool,
+) -> Result<u64, String> {
+    let members = members
+        .as_array()
+        .ok_or_else(|| format!("bin {} is not an array", members))?;
+    let mut mask = 0_u64;
+    for member in members {
+        let index = if values {
+            let value = member
+                .as_i64()
+                .and_then(|value| i32::try_from(value).ok())
+                .ok_or_else(|| format!("value {} is not a 32-bit integer", member))?;
+            unused_index(elements, *used, value)
+                .ok_or_else(|| format!("value {} doesn't match any unused element", value))?
+        } else {
+            let index = member
+                .as_u64()
+                .ok_or_else(|| format!("index {} is not a non-negative integer", member))?;
+            if index >= 64 {
+                return Err(format!("index {} is out of range", index));
+            }
+            index as usize
+        };
+        if mask & (1 << index) > 0 {
+            return Err(format!("element {} appears twice in the same bin", index));
+        }
+        mask |= 1 << index;
+        *used |= 1 << index;
+    }
+    Ok(mask)
+}
+
+// Bins are arrays of indices (batch output), arrays of values (values output, with --values), or
+// objects with "indices" and "sum" (json output). Claimed sums come from the bin objects or a
+// top level "sums" array, and default to the real sums.
+fn parse_claim(value: &Value, values: bool) -> Result<Claim, String> {
+    let elements: Vec<i32> = serde_json::from_value(value["elements"].clone())
+        .map_err(|err| format!("bad elements: {}", err))?;
+    if elements.len() > 64 {
+        return Err("at most 64 elements are supported".to_string());
+    }
+    let n = value["n"]
+        .as_u64()
+        .filter(|&n| n > 0 && n <= u64::from(u8::MAX))
+        .ok_or_else(|| "n must be an integer from 1 to 255".to_string())?;
+    let bins = value["bins"]
+        .as_array()
+        .ok_or_else(|| "bins must be an array".to_string())?;
+    let mut used = 0;
+    let mut partitions = Vec::with_capacity(bins.len());
+    for (bin, members) in bins.iter().enumerate() {
+        let (members, claimed) = match members {
+            Value::Object(record) => (&record["indices"], record.get("sum")),
+            members => (members, value["sums"].get(bin)),
+        };
+        let mask = parse_mask(members, &elements, &mut used, values)?;
+        let sum = match claimed {
+            Some(sum) => sum
+                .as_i64()
+                .and_then(|sum| i32::try_from(sum).ok())
+                .ok_or_else(|| format!("sum {} is not a 32-bit integer", sum))?,
+            None => Subset::new(mask, &elements).sum,
+        };
+        partitions.push(Subset { sum, mask });
+    }
+    Ok(Claim {
+        id: value.get("id").cloned().unwrap_or(Value::Null),
+        elements,
+        n: n as u8,
+        partitions,
+    })
+}
+
+fn check(value: &Value, opt: &VerifyOpt) -> Result<Value, String> {
+    let claim = parse_claim(value, opt.values)?;
+    let report = if opt.certify {
+        certify(&claim.elements, claim.n, &claim.partitions)
+    } else {
+        verify(&claim.elements, claim.n, &claim.partitions)
+    };
+    let report = report.map_err(|err| err.to_string())?;
+    let certificate = report.certificate.map(|certificate| match certificate {
+        Certificate::LowerBound => "lower_bound",
+        Certificate::Exhaustive => "exhaustive",
+    });
+    Ok(json!({
+        "id": claim.id,
+        "valid": true,
+        "score": report.score,
+        "lower_bound": report.lower_bound,
+        "gap": report.gap,
+        "certificate": certificate,
+    }))
+}
+
+// Checks every JSON document on the input, writing one JSON report per line. Returns whether all
+// of them were valid.
+pub fn run<R: Read, W: Write>(input: R, out: &mut W, opt: &VerifyOpt) -> io::Result<bool> {
+    let mut all_valid = true;
+    for value in serde_json::Deserializer::from_reader(input).into_iter::<Value>() {
+        let value = value.map_err(io::Error::from)?;
+        let report = check(&value, opt).unwrap_or_else(|err| {
+            all_valid = false;
+            json!({
+                "id": value.get("id").cloned().unwrap_or(Value::Null),
+                "valid": false,
+                "error": err,
+            })
+        });
+        writeln!(out, "{}", report)?;
+    }
+    Ok(all_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::verify::{run, VerifyOpt};
+    use serde_json::{json, Value};
+
+    fn check(input: &Value) -> (bool, Value) {
+        let opt = VerifyOpt {
+            values: false,
+            certify: false,
+        };
+        let mut out = Vec::new();
+        let valid = run(input.to_string().as_bytes(), &mut out, &opt).unwrap();
+        (valid, serde_json::from_slice(&out).unwrap())
+    }
+    #[test]
+    fn unit_64_elements() {
+        let elements: Vec<i32> = (1..=64).collect();
+        let evens: Vec<usize> = (0..64).step_by(2).collect();
+        let odds: Vec<usize> = (1..64).step_by(2).collect();
+        let (valid, report) = check(&json!({"elements": elements, "n": 2, "bins": [evens, odds]}));
+        assert!(valid, "{}", report);
+        assert_eq!(report["score"], 1056);
+        let elements: Vec<i32> = (1..=65).collect();
+        let (valid, report) = check(&json!({"elements": elements, "n": 1, "bins": [[]]}));
+        assert!(!valid);
+        assert_eq!(report["error"], "at most 64 elements are supported");
+    }
+}
@@ -1 +1,2 @@
+# Record main.profile first with: cargo run --release --features profiling -- solve --profile main.profile N method < elements.json
 PPROF_BINARY_PATH=$PWD/target/release/ pprof -http : -source_path=$PWD:"$(rustc --print sysroot)/lib/rustlib/src/rust/src" target/release/partition main.profile
@@ -0,0 +1,206 @@
+use crate::arith::Arith;
+use crate::large_kk;
+use crate::rng::XorShift;
+use crate::stats;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub seed: u64,
+    pub iterations: u64,
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            iterations: 1_000_000,
+            time_limit: None,
+        }
+    }
+}
+
+fn to_f64<T: Arith>(x: T) -> f64 {
+    x.to_f64().expect("element doesn't fit in an f64")
+}
+
+struct State<T> {
+    assignment: Vec<usize>,
+    sums: Vec<T>,
+}
+
+impl<T: Arith> State<T> {
+    fn score(&self) -> T {
+        *self.sums.iter().max().expect("sums is empty")
+    }
+    // Change in the sum of squared bin sums from moving `x` from bin `from` to bin `to`.
+    // Squares reward evening out any two bins, so the annealer gets a signal even when the
+    // move doesn't touch the largest bin.
+    fn move_energy(&self, x: T, from: usize, to: usize) -> f64 {
+        let x = to_f64(x);
+        2.0 * x * (x + to_f64(self.sums[to]) - to_f64(self.sums[from]))
+    }
+    fn move_element(&mut self, i: usize, x: T, to: usize) {
+        let from = self.assignment[i];
+        self.sums[from] -= x;
+        self.sums[to] += x;
+        self.assignment[i] = to;
+    }
+}
+
+// Simulated annealing over an element-to-bin assignment vector, so it isn't limited to the 64
+// elements a Subset mask can hold. Each step either moves one element to another bin or
+// swaps two elements in different bins, and the temperature falls geometrically over the
+// iteration budget. Returns the assignment with the smallest largest sum seen, early if the
+// time limit or the thread's deadline passes.
+pub fn anneal<T: Arith>(
+    elements: &[T],
+    n: u8,
+    initial: Vec<usize>,
+    options: &Options,
+) -> Vec<usize> {
+    let n = n as usize;
+    let mut sums = vec![T::from(0); n];
+    for (&x, &bin) in elements.iter().zip(initial.iter()) {
+        sums[bin] += x;
+    }
+    let mut state = State {
+        assignment: initial,
+        sums,
+    };
+    let mut best = state.assignment.clone();
+    let mut best_score = state.score();
+    if n < 2 || elements.len() < 2 {
+        return best;
+    }
+    let mean = to_f64(elements.iter().cloned().sum::<T>()) / elements.len() as f64;
+    let start_temperature = (mean * mean).max(1.0);
+    let end_temperature = 1e-3_f64.min(start_temperature);
+    let cooling = (end_temperature / start_temperature).powf(1.0 / options.iterations as f64);
+    let mut temperature = start_temperature;
+    let mut rng = XorShift::new(options.seed);
+    let start = Instant::now();
+    for iteration in 0..options.iterations {
+        if iteration % 1024 == 0
+            && (options
+                .time_limit
+                .is_some_and(|limit| start.elapsed() >= limit)
+                || stats::past_deadline())
+        {
+            break;
+        }
+        temperature *= cooling;
+        let i = rng.below(elements.len());
+        let from = state.assignment[i];
+        let accepted = if rng.below(2) == 0 {
+            let to = (from + 1 + rng.below(n - 1)) % n;
+            let energy = state.move_energy(elements[i], from, to);
+            let accept = energy <= 0.0 || rng.unit() < (-energy / temperature).exp();
+            if accept {
+                state.move_element(i, elements[i], to);
+            }
+            accept
+        } else {
+            let j = rng.below(elements.len());
+            let to = state.assignment[j];
+            if to == from || elements[i] <= elements[j] {
+                continue;
+            }
+            // Swapping is moving the difference from the bin holding the larger element.
+            let delta = elements[i] - elements[j];
+            let energy = state.move_energy(delta, from, to);
+            let accept = energy <= 0.0 || rng.unit() < (-energy / temperature).exp();
+            if accept {
+                state.move_element(i, elements[i], to);
+                state.move_element(j, elements[j], from);
+            }
+            accept
+        };
+        if accepted {
+            let score = state.score();
+            if score < best_score {
+                best_score = score;
+                best.clone_from(&state.assignment);
+            }
+        }
+    }
+    best
+}
+
+// Anneals from the multi-way Karmarkar-Karp assignment, which works at any input size.
+pub fn from_kk<T: Arith>(elements: &[T], n: u8, options: &Options) -> Vec<usize> {
+    let initial = large_kk::n_kk(elements, n);
+    anneal(elements, n, initial, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anneal::{anneal, Options};
+    use crate::benchmark_data;
+    use crate::ckk::n_kk;
+    use crate::large_kk;
+    use crate::select::{partition_using, PartitionMethod};
+    use crate::subset::to_assignment;
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn score(elements: &[i64], n: u8, assignment: &[usize]) -> i64 {
+        let mut sums = vec![0; n as usize];
+        for (x, &bin) in elements.iter().zip(assignment) {
+            sums[bin] += x;
+        }
+        sums.into_iter().max().unwrap()
+    }
+    proptest! {
+        #[test]
+        fn prop_anneal_beats_n_kk(ref elements in vec(1_i32..1000, 1..20), n in 2_u8..5) {
+            let elements: Vec<i64> = elements.iter().map(|&x| x.into()).collect();
+            let kk_score = score(&elements, n, &large_kk::n_kk(&elements, n));
+            let method = PartitionMethod::Anneal { seed: 0, iterations: 10_000 };
+            let partitions = partition_using(method, &elements, n).to_subsets(&elements);
+            assert_eq!(partitions.len(), n as usize);
+            assert!(partitions[0].sum <= kk_score);
+       }
+    }
+    #[test]
+    fn unit_anneal_reproducible() {
+        let elements: Vec<i64> = benchmark_data::BIG_ELEMENTS
+            .iter()
+            .map(|&x| x.into())
+            .collect();
+        let initial = to_assignment(&n_kk(&elements, 4).partitions, elements.len());
+        let options = Options {
+            seed: 17,
+            iterations: 10_000,
+            time_limit: None,
+        };
+        let first = anneal(&elements, 4, initial.clone(), &options);
+        let second = anneal(&elements, 4, initial, &options);
+        assert_eq!(first, second);
+    }
+    #[test]
+    fn unit_anneal_large() {
+        let elements: Vec<i64> = (0..20_000).map(|i| (i * 7919) % 10_007 + 1).collect();
+        let initial: Vec<usize> = (0..elements.len()).map(|i| (i / 4) % 3).collect();
+        let initial_score = score(&elements, 3, &initial);
+        let options = Options {
+            iterations: 100_000,
+            ..Options::default()
+        };
+        let result = anneal(&elements, 3, initial, &options);
+        assert_eq!(result.len(), elements.len());
+        assert!(score(&elements, 3, &result) <= initial_score);
+    }
+    #[test]
+    fn unit_anneal_beyond_masks() {
+        let elements: Vec<i64> = (1..=100).collect();
+        let method = PartitionMethod::Anneal {
+            seed: 0,
+            iterations: 10_000,
+        };
+        let solution = partition_using(method, &elements, 2);
+        assert_eq!(solution.assignment.len(), elements.len());
+        assert_eq!(solution.score, 2525);
+    }
+}
@@ -1,13 +1,35 @@
-use num::Integer;
+use num::{Integer, ToPrimitive};
 use std::convert::From;
 use std::fmt::{Debug, Display};
+use std::hash::Hash;
 use std::iter::Sum;
 use std::ops::{AddAssign, SubAssign};
 
 pub trait Arith:
-    Integer + AddAssign + SubAssign + From<u8> + Clone + Copy + Sum + Debug + Display
+    Integer
+    + ToPrimitive
+    + AddAssign
+    + SubAssign
+    + From<u8>
+    + Clone
+    + Copy
+    + Sum
+    + Debug
+    + Display
+    + Hash
 {
 }
 impl<T> Arith for T where
-    T: Integer + AddAssign + SubAssign + From<u8> + Clone + Copy + Sum + Debug + Display
-{}
+    T: Integer
+        + ToPrimitive
+        + AddAssign
+        + SubAssign
+        + From<u8>
+        + Clone
+        + Copy
+        + Sum
+        + Debug
+        + Display
+        + Hash
+{
+}
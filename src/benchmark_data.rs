@@ -5,13 +5,13 @@ pub const BIG_ELEMENTS: [i32; 30] = [
     2057084, 9599726, 9599726, 9599726, 9599726, 9599726, 9599726, 537584, 537584, 537584,
 ];
 
-#[allow(clippy::unreadable_literal)]
+#[allow(clippy::unreadable_literal, dead_code)]
 pub const MEDIUM_ELEMENTS: [i32; 20] = [
     403188, 4114168, 4114168, 5759835, 5759835, 5759835, 2879917, 8228336, 8228336, 8228336,
     8228336, 8228336, 8228336, 8228336, 2057084, 2057084, 2057084, 2057084, 2057084, 2057084,
 ];
 
-#[allow(clippy::unreadable_literal)]
+#[allow(clippy::unreadable_literal, dead_code)]
 pub const SMALL_ELEMENTS: [i32; 12] = [
     403188, 4114168, 4114168, 5759835, 5759835, 5759835, 2879917, 8228336, 8228336, 8228336,
     8228336, 8228336,
@@ -0,0 +1,147 @@
+use crate::arith::Arith;
+use std::cmp::Reverse;
+use std::iter;
+
+// Lower bounds on the best achievable score (the largest bin sum), cheap enough to compute
+// alongside any heuristic so its result can be compared against them.
+
+// Sums of the first i sorted elements, so any run of them sums in O(1).
+fn prefix_sums<T: Arith>(sorted: &[T]) -> Vec<T> {
+    let mut prefix = Vec::with_capacity(sorted.len() + 1);
+    prefix.push(T::from(0));
+    for &x in sorted {
+        let last = *prefix.last().unwrap();
+        prefix.push(last + x);
+    }
+    prefix
+}
+
+// Among the k*n + 1 largest elements, some bin has to get at least k + 1, so it holds at least
+// the k + 1 smallest of them. k = 1 is the sum of the n-th and (n+1)-th largest elements.
+// Takes the prefix sums of the elements sorted in descending order.
+fn pigeonhole_bound<T: Arith>(prefix: &[T], n: usize) -> T {
+    let len = prefix.len() - 1;
+    let mut best = T::from(0);
+    let mut k = 1;
+    while k * n < len {
+        best = best.max(prefix[k * n + 1] - prefix[k * n - k]);
+        k += 1;
+    }
+    best
+}
+
+// Martello and Toth's L2 bound on the number of bins of this capacity the elements need, which
+// is at least as strong as the LP relaxation of bin packing. For each threshold k, elements
+// over capacity - k can't share with anything of size k or more, elements over half the
+// capacity need a bin each, and the elements from k to half the capacity have to fit in the
+// space those leave or open new bins.
+fn bins_needed<T: Arith>(sorted: &[T], prefix: &[T], capacity: T) -> usize {
+    // The number of elements greater than, or at least, a value.
+    let above = |x: T| sorted.partition_point(|&y| y > x);
+    let from = |x: T| sorted.partition_point(|&y| y >= x);
+    let half = capacity / T::from(2);
+    let thresholds = sorted[above(half)..].iter().cloned();
+    iter::once(T::from(0))
+        .chain(thresholds)
+        .map(|k| {
+            // The large elements over capacity - k need a bin each whatever else happens; the
+            // rest of them and the small elements need at least their total over the capacity.
+            let alone = above(capacity - k);
+            let large = above(half);
+            let (whole, part) = (prefix[from(k)] - prefix[alone]).div_rem(&capacity);
+            let shared = whole.to_usize().expect("bin count doesn't fit in a usize");
+            large.max(alone + shared + usize::from(part > T::from(0)))
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// The smallest capacity, at least `known`, for which bins_needed doesn't rule out n bins. A
+// capacity is only skipped once bins_needed shows it's too small, so the result is a valid
+// bound even where bins_needed isn't monotonic.
+fn bin_packing_bound<T: Arith>(sorted: &[T], prefix: &[T], n: usize, known: T) -> T {
+    let mut low = known;
+    let mut high = prefix[sorted.len()].max(known);
+    while low < high {
+        let capacity = low + (high - low) / T::from(2);
+        if bins_needed(sorted, prefix, capacity) > n {
+            low = capacity + T::from(1);
+        } else {
+            high = capacity;
+        }
+    }
+    low
+}
+
+// The largest of: the perfect partition score ceil(total/n), the largest element, the
+// pigeonhole bounds on the largest elements, and the bin packing bound. Zero for an empty
+// instance.
+pub fn lower_bound<T: Arith>(elements: &[T], n: u8) -> T {
+    let mut sorted = elements.to_vec();
+    sorted.sort_unstable_by_key(|&x| Reverse(x));
+    let prefix = prefix_sums(&sorted);
+    let total = prefix[sorted.len()];
+    let perfect = (total + T::from(n - 1)) / n.into();
+    let largest = sorted.first().cloned().unwrap_or_else(|| T::from(0));
+    let cheap = perfect
+        .max(largest)
+        .max(pigeonhole_bound(&prefix, n as usize));
+    if cheap == T::from(0) {
+        return cheap;
+    }
+    bin_packing_bound(&sorted, &prefix, n as usize, cheap)
+}
+
+// How far a score is above the lower bound, as a fraction of the bound.
+pub fn relative_gap<T: Arith>(score: T, lower_bound: T) -> f64 {
+    let bound = lower_bound.to_f64().expect("bound doesn't fit in an f64");
+    if bound == 0.0 {
+        return 0.0;
+    }
+    let score = score.to_f64().expect("score doesn't fit in an f64");
+    (score - bound) / bound
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::{lower_bound, relative_gap};
+    use crate::select::{partition_using, PartitionMethod};
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    proptest! {
+        #[test]
+        fn prop_lower_bound_gcc(ref elements in vec(1_i32..1000, 1..10), n in 2_u8..5) {
+            let partitions = partition_using(PartitionMethod::GCC, elements, n).to_subsets(elements);
+            let optimal = partitions.iter().map(|subset| subset.sum).max().unwrap();
+            assert!(lower_bound(elements, n) <= optimal);
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_partition_using_bound(ref elements in vec(1_i32..1000, 1..20), n in 2_u8..5) {
+            let result = partition_using(PartitionMethod::KK, elements, n);
+            assert!(result.gap().unwrap() >= 0.0);
+            assert_eq!(result.lower_bound, Some(lower_bound(elements, n)));
+        }
+    }
+    #[test]
+    fn unit_lower_bound() {
+        assert_eq!(lower_bound(&[5, 4, 3, 3, 3], 2), 9);
+        assert_eq!(lower_bound(&[10, 1, 1], 2), 10);
+        // ceil(total/n) is 8, but two of the four 5s must share a bin.
+        assert_eq!(lower_bound(&[5, 5, 5, 5, 1, 1], 3), 10);
+        // Some bin gets three of the seven largest.
+        assert_eq!(lower_bound(&[4, 4, 4, 4, 4, 4, 4], 3), 12);
+        assert_eq!(lower_bound::<i32>(&[], 3), 0);
+        // The cheap bounds give 15, but a bin of 15 can hold 12 or two 7s, not both, so there's
+        // no room left for all of them in two bins.
+        assert_eq!(lower_bound(&[12, 7, 7, 4], 2), 16);
+        assert_eq!(lower_bound(&[17, 17, 14, 10, 10, 7], 3), 27);
+    }
+    #[test]
+    fn unit_relative_gap() {
+        assert_eq!(relative_gap(12, 10), 0.2);
+        assert_eq!(relative_gap(0, 0), 0.0);
+    }
+}
@@ -1,16 +1,46 @@
-use arith::Arith;
+use crate::arith::Arith;
+use crate::gcc::Limits;
+use crate::multiset::Multiset;
+use crate::stats;
+use crate::subset::{submasks, Subset};
+use std::cmp::Reverse;
 use std::iter::{empty, once};
-use subset::{submasks, Subset};
 
+// What a bin has to satisfy. Bins are generated in descending order of their sums, and pairing
+// the largest sums with the largest capacities fits them if any pairing does.
+struct Rules<T> {
+    // None when copies can't be treated as interchangeable.
+    multiset: Option<Multiset<T>>,
+    // In descending order.
+    capacities: Option<Vec<T>>,
+    // The masks of pairs that can't share a bin.
+    apart: Vec<u64>,
+}
+
+impl<T: Arith> Rules<T> {
+    fn allows(&self, subset: &Subset<T, u64>, bin: usize) -> bool {
+        self.apart.iter().all(|&pair| subset.mask & pair != pair)
+            && self
+                .capacities
+                .as_ref()
+                .is_none_or(|capacities| subset.sum <= capacities[bin])
+    }
+}
+
+// Bins only take the lowest-indexed copies of each value left, so partitionings that just swap
+// copies around are generated once.
 fn all_partitions<'a, T: Arith>(
     mask: u64,
     elements: &'a [T],
+    rules: &'a Rules<T>,
+    bin: usize,
     n: u8,
     max: T,
-) -> Box<Iterator<Item = Vec<Subset<T, u64>>> + 'a> {
+) -> Box<dyn Iterator<Item = Vec<Subset<T, u64>>> + 'a> {
+    stats::count_node();
     if n == 1 {
         let subset = Subset::new(mask, elements);
-        if subset.sum <= max {
+        if subset.sum <= max && rules.allows(&subset, bin) {
             Box::new(once(vec![subset]))
         } else {
             Box::new(empty())
@@ -18,32 +48,69 @@ fn all_partitions<'a, T: Arith>(
     } else {
         Box::new(
             submasks(mask)
+                .filter(move |&submask| {
+                    rules
+                        .multiset
+                        .as_ref()
+                        .is_none_or(|multiset| multiset.is_canonical(submask, mask))
+                })
                 .filter_map(move |submask| {
                     let subset = Subset::new(submask, elements);
-                    if subset.sum <= max {
+                    if subset.sum <= max && rules.allows(&subset, bin) {
                         Some(subset)
                     } else {
                         None
                     }
                 })
                 .flat_map(move |subset| {
-                    all_partitions(mask ^ subset.mask, elements, n - 1, subset.sum).map(
-                        move |mut rest| {
-                            rest.push(subset.clone());
-                            rest
-                        },
+                    all_partitions(
+                        mask ^ subset.mask,
+                        elements,
+                        rules,
+                        bin + 1,
+                        n - 1,
+                        subset.sum,
                     )
+                    .map(move |mut rest| {
+                        rest.push(subset.clone());
+                        rest
+                    })
                 }),
         )
     }
 }
 
 pub fn partition<T: Arith>(elements: &[T], n: u8) -> Vec<Subset<T, u64>> {
-    let mask = (1 << elements.len()) - 1;
+    partition_with_limits(elements, n, &Limits::none()).expect("nothing is ruled out")
+}
+
+// The best partitioning that keeps to the limits, if any does.
+pub fn partition_with_limits<T: Arith>(
+    elements: &[T],
+    n: u8,
+    limits: &Limits<T>,
+) -> Option<Vec<Subset<T, u64>>> {
+    let mask = Subset::all(elements).mask;
     let total = elements.iter().fold(T::from(0), |acc, &x| acc + x);
-    let mut out = all_partitions(mask, elements, n, total)
-        .min_by_key(|partitioning| Some(partitioning.last()?.sum))
-        .unwrap();
+    let capacities = limits.capacities.clone().map(|mut capacities| {
+        capacities.sort_by_key(|&capacity| Reverse(capacity));
+        capacities
+    });
+    let rules = Rules {
+        multiset: if limits.apart.is_empty() {
+            Some(Multiset::new(elements))
+        } else {
+            None
+        },
+        capacities,
+        apart: limits
+            .apart
+            .iter()
+            .map(|&(a, b)| (1 << a) | (1 << b))
+            .collect(),
+    };
+    let mut out = all_partitions(mask, elements, &rules, 0, n, total)
+        .min_by_key(|partitioning| Some(partitioning.last()?.sum))?;
     out.reverse();
-    out
+    Some(out)
 }
@@ -1,5 +1,7 @@
 use super::arith::Arith;
+use super::stats;
 use super::subset::Subset;
+use super::transposition::Table;
 use itertools::Itertools;
 use std::cmp::Ordering;
 use std::cmp::Reverse;
@@ -8,6 +10,7 @@ use std::iter::Iterator;
 use std::mem::swap;
 
 #[derive(Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KKPartition<T: Arith> {
     pub left: Subset<T, u64>,
     pub right: Subset<T, u64>,
@@ -20,7 +23,7 @@ impl<T: Arith> PartialEq for KKPartition<T> {
 }
 impl<T: Arith> PartialOrd for KKPartition<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.delta().cmp(&other.delta()))
+        Some(self.cmp(other))
     }
 }
 impl<T: Arith> Ord for KKPartition<T> {
@@ -147,7 +150,21 @@ fn old_raw<T: Arith>(
     }
 }
 
+// Unlike the searches that place elements in bins, this needs no multiset handling: it branches
+// on whether to difference or sum the two largest numbers, which gives different numbers even
+// when they're copies of one value.
 pub fn ckk<T: Arith>(elements: &[T]) -> KKPartition<T> {
+    search(elements, None)
+}
+
+// Skips sets of remaining numbers reached again by another sequence of differences and sums.
+// The table is cleared first, so it can be reused across instances to accumulate statistics.
+pub fn with_table<T: Arith>(elements: &[T], table: &mut Table<T>) -> KKPartition<T> {
+    table.clear();
+    search(elements, Some(table))
+}
+
+fn search<T: Arith>(elements: &[T], mut table: Option<&mut Table<T>>) -> KKPartition<T> {
     let mut best_directions = Vec::with_capacity(elements.len());
     let mut directions = Vec::with_capacity(elements.len());
     let mut best = elements.iter().cloned().sum();
@@ -159,6 +176,7 @@ pub fn ckk<T: Arith>(elements: &[T]) -> KKPartition<T> {
         &mut directions,
         &mut best,
         &mut best_directions,
+        &mut table,
     );
     reconstruct_ckk(elements, best_directions)
 }
@@ -177,8 +195,44 @@ pub fn from_subset<T: Arith>(subset: &Subset<T, u64>, elements: &[T]) -> KKParti
         &mut directions,
         &mut best,
         &mut best_directions,
+        &mut None,
     );
-    reconstruct_ckk(&masked_elements, best_directions)
+    let partition = reconstruct_ckk(&masked_elements, best_directions);
+    KKPartition {
+        left: Subset::new(deposit(partition.left.mask, subset.mask), elements),
+        right: Subset::new(deposit(partition.right.mask, subset.mask), elements),
+    }
+}
+
+// Moves bit k of `mask` to the k-th set bit of `within`, taking indices into the elements a
+// subset holds back to indices into all the elements.
+fn deposit(mask: u64, within: u64) -> u64 {
+    let mut out = 0;
+    let mut remaining = within;
+    let mut bit = 0;
+    while remaining != 0 {
+        let lowest = remaining & remaining.wrapping_neg();
+        if mask & (1 << bit) != 0 {
+            out |= lowest;
+        }
+        remaining ^= lowest;
+        bit += 1;
+    }
+    out
+}
+
+// Whether these remaining numbers have been searched before. If so, whatever they lead to beat
+// the best at the time or didn't, and the best has only improved since.
+fn seen<T: Arith>(table: &mut Option<&mut Table<T>>, first: T, tail: &[T]) -> bool {
+    match table {
+        Some(table) => {
+            let mut numbers = tail.to_vec();
+            numbers.push(first);
+            numbers.sort_unstable();
+            table.visit(0, &numbers)
+        }
+        None => false,
+    }
 }
 
 // When ckk_raw returns, elements must:
@@ -190,7 +244,9 @@ fn ckk_raw<T: Arith>(
     directions: &mut Vec<Direction>,
     best: &mut T,
     best_directions: &mut Vec<Direction>,
+    table: &mut Option<&mut Table<T>>,
 ) {
+    stats::count_node();
     let (first, tail) = elements.split_first_mut().expect("elements is empty");
     let original_first = *first;
     let snd_val: T = match tail.split_first_mut() {
@@ -235,21 +291,24 @@ fn ckk_raw<T: Arith>(
         }
         panic!("Couldn't find the original first");
     }
-    directions.push(Direction::Diff);
-    tail[0] = *first - snd_val;
-    ckk_raw(
-        tail,
-        sum - snd_val - snd_val,
-        directions,
-        best,
-        best_directions,
-    );
-    directions.pop();
-    directions.push(Direction::Sum);
-    tail[0] = *first + snd_val;
-    ckk_raw(tail, sum, directions, best, best_directions);
-    directions.pop();
-    tail[0] = snd_val;
+    if !seen(table, *first, tail) {
+        directions.push(Direction::Diff);
+        tail[0] = *first - snd_val;
+        ckk_raw(
+            tail,
+            sum - snd_val - snd_val,
+            directions,
+            best,
+            best_directions,
+            table,
+        );
+        directions.pop();
+        directions.push(Direction::Sum);
+        tail[0] = *first + snd_val;
+        ckk_raw(tail, sum, directions, best, best_directions, table);
+        directions.pop();
+        tail[0] = snd_val;
+    }
     if *first == original_first {
         return;
     }
@@ -263,6 +322,7 @@ fn ckk_raw<T: Arith>(
 }
 
 #[derive(Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Partitioning<T: Arith> {
     pub partitions: Vec<Subset<T, u64>>,
 }
@@ -274,7 +334,7 @@ impl<T: Arith> PartialEq for Partitioning<T> {
 }
 impl<T: Arith> PartialOrd for Partitioning<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.delta().cmp(&other.delta()))
+        Some(self.cmp(other))
     }
 }
 impl<T: Arith> Ord for Partitioning<T> {
@@ -298,7 +358,7 @@ impl<T: Arith> Partitioning<T> {
             .iter_mut()
             .zip(other.partitions.into_iter().rev())
         {
-            *s = Subset::union(&s, &o);
+            *s = Subset::union(s, &o);
         }
         self.partitions.sort_unstable_by_key(|x| Reverse(x.sum));
     }
@@ -327,15 +387,14 @@ pub fn n_kk<T: Arith>(elements: &[T], n: u8) -> Partitioning<T> {
 
 #[cfg(test)]
 mod tests {
-    extern crate test;
-    use self::test::Bencher;
-    use arith::Arith;
-    use benchmark_data;
-    use ckk;
-    use ckk::{ckk, ckk_raw, kk, n_kk, old, old_raw};
+    use crate::arith::Arith;
+    use crate::ckk;
+    use crate::ckk::{ckk, ckk_raw, kk, n_kk, old, old_raw, with_table};
+    use crate::subset::Subset;
+    use crate::transposition::Table;
     use proptest::collection::vec;
+    use proptest::proptest;
     use proptest::strategy::Strategy;
-    use subset::Subset;
     proptest! {
         #[test]
         fn prop_compare_raw(ref elements in vec(1_i32..100, 1..10)) {
@@ -349,7 +408,7 @@ mod tests {
             let mut best_2 = elements.iter().cloned().sum();
             let mut work_elements_2 = elements.to_vec();
             let sum = elements.iter().cloned().sum();
-            ckk_raw(&mut work_elements_2, sum, &mut directions_2, &mut best_2, &mut best_directions_2);
+            ckk_raw(&mut work_elements_2, sum, &mut directions_2, &mut best_2, &mut best_directions_2, &mut None);
             assert_eq!(best_directions_1,  best_directions_2);
        }
     }
@@ -361,6 +420,23 @@ mod tests {
             assert_eq!(partition_1, partition_2);
        }
     }
+    proptest! {
+        #[test]
+        fn prop_ckk_table(ref elements in vec(1_i32..20, 2..12), bytes in 0_usize..4096) {
+            let mut table = Table::with_memory(bytes);
+            let partition = with_table(elements, &mut table);
+            assert_eq!(partition, ckk(elements));
+            assert_eq!(partition.left.sum + partition.right.sum, elements.iter().sum::<i32>());
+            assert_eq!(partition.left.mask ^ partition.right.mask, (1 << elements.len()) - 1);
+       }
+    }
+    #[test]
+    fn unit_ckk_table() {
+        let elements = [8, 7, 6, 5, 4, 4, 3, 3, 2, 1];
+        let mut table = Table::with_memory(1 << 16);
+        assert_eq!(with_table(&elements, &mut table).delta(), 1);
+        assert!(table.stats().hits > 0);
+    }
     #[test]
     fn unit_ckk() {
         let elements = vec![2, 3, 4, 5];
@@ -380,7 +456,7 @@ mod tests {
         #[test]
         fn from_subset(ref elements_subset in elements_and_subset(vec(1_i32..100, 1..30))) {
             let (elements, subset) = elements_subset;
-            let masked_elements = subset.to_vec(&elements);
+            let masked_elements = subset.to_vec(elements);
 
             let partition_1 = ckk(&masked_elements);
             let mut partition_1_vec = vec![
@@ -391,10 +467,11 @@ mod tests {
                 v.sort();
             }
 
-            let partition_2 = ckk::from_subset(&subset, &elements);
+            let partition_2 = ckk::from_subset(subset, elements);
+            assert_eq!(partition_2.left.mask | partition_2.right.mask, subset.mask);
             let mut partition_2_vec = vec![
-                partition_2.left.to_vec(&masked_elements),
-                partition_2.right.to_vec(&masked_elements),
+                partition_2.left.to_vec(elements),
+                partition_2.right.to_vec(elements),
             ];
             for v in partition_2_vec.iter_mut() {
                 v.sort();
@@ -411,12 +488,21 @@ mod tests {
             assert_eq!(partition_1, partition_2);
        }
     }
-    #[bench]
-    fn bench_ckk(b: &mut Bencher) {
-        b.iter(|| old(&benchmark_data::SMALL_ELEMENTS));
-    }
-    #[bench]
-    fn bench_ckk_2(b: &mut Bencher) {
-        b.iter(|| ckk(&benchmark_data::SMALL_ELEMENTS));
+    #[cfg(feature = "serde")]
+    #[test]
+    fn unit_serde() {
+        let elements = [24, 17, 24, 25, 25];
+        let partition = ckk(&elements);
+        let json = serde_json::to_string(&partition).unwrap();
+        assert_eq!(
+            json,
+            r#"{"left":{"sum":65,"mask":7},"right":{"sum":50,"mask":24}}"#
+        );
+        let parsed: ckk::KKPartition<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_vec(), partition.to_vec());
+        let partitioning = n_kk(&elements, 3);
+        let json = serde_json::to_string(&partitioning).unwrap();
+        let parsed: ckk::Partitioning<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.partitions, partitioning.partitions);
     }
 }
@@ -45,7 +45,7 @@ impl<T, I: Iterator<Item = T>> LazyQueue<T, I> {
     }
 }
 impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T, u64>>> Iterator
-    for ESS<T, I1, I2> where
+    for ESS<T, I1, I2>
 {
     type Item = Subset<T, u64>;
     fn next(&mut self) -> Option<Subset<T, u64>> {
@@ -74,9 +74,13 @@ impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T
                 self.next()
             }
             (true, false) => {
-                //Too small. Descending will only decrease, so drop the current ascending.
-                assert_eq!(self.ascending_index, 0);
-                self.ascending.pop();
+                //Too small. Descending will only decrease, so drop the current ascending, along
+                //with the smaller ones before it. Those can only be left over from before the
+                //range was restricted.
+                for _ in 0..=self.ascending_index {
+                    self.ascending.pop();
+                }
+                self.ascending_index = 0;
                 self.next()
             }
             (true, true) => unreachable!(),
@@ -85,7 +89,7 @@ impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T
 }
 
 impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T, u64>>>
-    ESS<T, I1, I2> where
+    ESS<T, I1, I2>
 {
     pub fn restrict_range(&mut self, range: Range<T>) -> Option<()> {
         if range.is_empty() {
@@ -127,7 +131,7 @@ pub fn iterate_subsets_in_range<T: Arith>(
 }
 
 impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T, u64>>>
-    ESS<T, I1, I2> where
+    ESS<T, I1, I2>
 {
     fn step_descending(&mut self) {
         self.descending
@@ -142,7 +146,7 @@ pub struct BiasedESS<T, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item =
     first: Subset<T, u64>,
 }
 impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T, u64>>> Iterator
-    for BiasedESS<T, I1, I2> where
+    for BiasedESS<T, I1, I2>
 {
     type Item = Subset<T, u64>;
     fn next(&mut self) -> Option<Subset<T, u64>> {
@@ -152,7 +156,7 @@ impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T
     }
 }
 impl<T: Arith, I1: Iterator<Item = Subset<T, u64>>, I2: Iterator<Item = Subset<T, u64>>>
-    BiasedESS<T, I1, I2> where
+    BiasedESS<T, I1, I2>
 {
     pub fn restrict_range(&mut self, range: Range<T>) -> Option<()> {
         let shifted_range =
@@ -177,21 +181,81 @@ pub fn biased_iterate_subsets_in_range<T: Arith>(
     let ess = iterate_subsets_in_range(rest_mask, elements, shifted_range);
     BiasedESS { first, ess }
 }
+// How many pairs of subsets, one from each side, sum to less than the bound. The right side is
+// walked downwards while the left goes up, so every right subset still waiting pairs with the
+// current left one.
+fn count_pairs_below<T: Arith>(left: u64, right: u64, elements: &[T], bound: T) -> u64 {
+    let ascending: OrderedSubsets<_, Up> = ordered_subsets(left, elements);
+    let descending_raw: OrderedSubsets<_, Down> = ordered_subsets(right, elements);
+    let mut descending = descending_raw.peekable();
+    let mut remaining = 1_u64 << right.count_ones();
+    let mut count = 0;
+    for small in ascending {
+        while let Some(large) = descending.peek() {
+            if small.sum + large.sum < bound {
+                break;
+            }
+            descending.next();
+            remaining -= 1;
+        }
+        if remaining == 0 {
+            break;
+        }
+        count += remaining;
+    }
+    count
+}
+
+// Counts what iterate_subsets_in_range would produce, without building any of the subsets.
+// Zeros don't change any sum, so they're left out of the search and each doubles the count.
+// Panics if the count doesn't fit in a u64.
+pub fn count_subsets_in_range<T: Arith>(mask: u64, elements: &[T], range: Range<T>) -> u64 {
+    if range.is_empty() {
+        return 0;
+    }
+    let zeros = (0..elements.len())
+        .filter(|&i| mask & (1 << i) > 0 && elements[i] == T::from(0))
+        .fold(0_u64, |zeros, i| zeros | (1 << i));
+    let (left, right) = split_mask(mask & !zeros, elements);
+    let count = count_pairs_below(left, right, elements, range.end)
+        - count_pairs_below(left, right, elements, range.start);
+    (0..zeros.count_ones())
+        .try_fold(count, |count, _| count.checked_mul(2))
+        .expect("count doesn't fit in a u64")
+}
+
+// Two-way partitions whose sums differ by at most one, each counted once rather than once per
+// side.
+pub fn count_perfect_partitions<T: Arith>(elements: &[T]) -> u64 {
+    let all = Subset::all(elements);
+    let half = all.sum / T::from(2);
+    let count = count_subsets_in_range(all.mask, elements, half..half + T::from(1));
+    if all.sum.is_odd() || elements.is_empty() {
+        // Every subset summing to the lower half has a complement summing to the upper one.
+        count
+    } else {
+        // Both sides sum to the half, so every partition was counted twice.
+        count / 2
+    }
+}
 #[cfg(test)]
 mod tests {
-    use arith::Arith;
-    use ess::{iterate_subsets_in_range, Subset};
+    use crate::arith::Arith;
+    use crate::ess::{
+        count_perfect_partitions, count_subsets_in_range, iterate_subsets_in_range, Subset,
+    };
+    use crate::subset::{all_subsets, submasks};
     use proptest::collection::vec;
+    use proptest::proptest;
     use std::collections::HashMap;
     use std::fmt::Debug;
     use std::hash::Hash;
     use std::ops::Range;
-    use subset::{all_subsets, submasks};
 
     fn naive_subsets_in_range<T: Arith>(
         elements: &[T],
         range: Range<T>,
-    ) -> Option<(Vec<Subset<T, u64>>)> {
+    ) -> Option<Vec<Subset<T, u64>>> {
         let mut subsets = all_subsets(elements)?;
         subsets.retain(|subset| range.contains(&subset.sum));
         Some(subsets)
@@ -276,13 +340,15 @@ mod tests {
         let mask = (1 << elements.len()) - 1;
         let expected: Vec<Subset<i32, u64>> =
             naive_subsets_in_range(elements, range.clone()).unwrap();
+        let count = count_subsets_in_range(mask, elements, range.clone());
+        assert_eq!(count, expected.len() as u64);
         let actual = iterate_subsets_in_range(mask, elements, range);
         assert_permutation(expected.into_iter(), actual);
     }
 
     proptest! {
         #[test]
-        fn prop_iterate_subsets_in_range(ref elements in vec(1_i32..100, 1..10), b1 in 1_i32..100, b2 in 1_i32..100) {
+        fn prop_iterate_subsets_in_range(ref elements in vec(0_i32..100, 1..10), b1 in 1_i32..100, b2 in 1_i32..100) {
             let range = if b1 < b2 {
                 b1..b2
             } else {
@@ -306,4 +372,15 @@ mod tests {
         let actual = iterate_subsets_in_range(mask, &elements, range);
         assert_permutation(expected.into_iter(), actual);
     }
+    #[test]
+    fn unit_count_perfect_partitions() {
+        assert_eq!(count_perfect_partitions(&[1, 2, 3]), 1);
+        assert_eq!(count_perfect_partitions(&[1, 1, 1, 1]), 3);
+        assert_eq!(count_perfect_partitions(&[1, 2, 4]), 1);
+        assert_eq!(count_perfect_partitions(&[1, 5]), 0);
+        assert_eq!(count_perfect_partitions::<i32>(&[]), 1);
+        let mut elements = vec![0; 64];
+        elements[..4].copy_from_slice(&[1, 1, 2, 2]);
+        assert_eq!(count_perfect_partitions(&elements), 1 << 61);
+    }
 }
@@ -1,10 +1,13 @@
-extern crate cpuprofiler;
-extern crate num;
-
 use super::arith::Arith;
+use crate::bounds;
+use crate::local_search::bits;
+use crate::multiset::Multiset;
+use crate::stats;
+use crate::subset::Subset;
+use crate::transposition::Table;
+use std::cmp::Reverse;
 use std::iter::Iterator;
 use std::mem;
-use subset::Subset;
 
 fn consider_partitioning<T: Arith>(
     current_best: &mut (Vec<Subset<T, u64>>, T),
@@ -18,10 +21,83 @@ fn consider_partitioning<T: Arith>(
     }
 }
 
-#[derive(Clone, Copy)]
+// Restrictions on where elements can go, beyond the number of bins. The search enforces them
+// as it places elements, so it only ever finds partitionings that keep to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits<T> {
+    // The most each bin can hold, one capacity per bin.
+    pub capacities: Option<Vec<T>>,
+    // Pairs of element indices that can't share a bin.
+    pub apart: Vec<(usize, usize)>,
+}
+
+impl<T> Limits<T> {
+    pub fn none() -> Self {
+        Self {
+            capacities: None,
+            apart: Vec::new(),
+        }
+    }
+}
+
+// Everything about the instance the search needs besides the elements themselves.
 struct Constants<T: Arith> {
     total: T,
     n_partitions: T,
+    // No partitioning scores less, so finding one that scores this ends the search.
+    lower_bound: T,
+    // The sum of the elements from each index on.
+    remaining: Vec<T>,
+    previous_copies: Vec<Option<usize>>,
+    capacities: Option<Vec<T>>,
+    // For each index, the indices it can't share a bin with.
+    conflicts: Vec<u64>,
+}
+
+impl<T: Arith> Constants<T> {
+    // Whether bins can be told apart by more than their sums.
+    fn limited(&self) -> bool {
+        self.capacities.is_some() || self.conflicts.iter().any(|&conflicts| conflicts != 0)
+    }
+
+    // Whether the element at `index` can go in this bin.
+    fn allows(&self, bin: usize, partition: &Subset<T, u64>, index: usize, x: T) -> bool {
+        partition.mask & self.conflicts[index] == 0
+            && self
+                .capacities
+                .as_ref()
+                .is_none_or(|capacities| partition.sum + x <= capacities[bin])
+    }
+}
+
+// A transposition table, and for each index whether states there can be looked up in it. The
+// search below a state only depends on the sorted bin sums and the index if no copy placed
+// before the index constrains the bins of copies after it.
+struct Memo<'a, T> {
+    table: &'a mut Table<T>,
+    independent: Vec<bool>,
+}
+
+impl<'a, T: Arith> Memo<'a, T> {
+    fn new(table: &'a mut Table<T>, previous_copies: &[Option<usize>]) -> Self {
+        let mut independent = vec![true; previous_copies.len()];
+        let mut earliest = usize::MAX;
+        for index in (0..previous_copies.len()).rev() {
+            earliest = earliest.min(previous_copies[index].unwrap_or(usize::MAX));
+            independent[index] = earliest >= index;
+        }
+        Self { table, independent }
+    }
+
+    // Whether this state has already been explored.
+    fn seen(&mut self, index: usize, partitions: &[Subset<T, u64>]) -> bool {
+        if !self.independent[index] {
+            return false;
+        }
+        let mut sums: Vec<T> = partitions.iter().map(|partition| partition.sum).collect();
+        sums.sort_unstable();
+        self.table.visit(index, &sums)
+    }
 }
 
 fn expand_partitions<T: Arith>(
@@ -29,28 +105,93 @@ fn expand_partitions<T: Arith>(
     index: usize,
     partitions: &mut [Subset<T, u64>],
     current_best: &mut (Vec<Subset<T, u64>>, T),
-    constants: Constants<T>,
+    constants: &Constants<T>,
+    memo: &mut Option<Memo<T>>,
 ) {
+    stats::count_node();
     if elements.len() <= index {
         consider_partitioning(current_best, partitions);
         return;
     }
     let largest_sum = score_partitioning(partitions);
-    if largest_sum >= (*current_best).1 {
+    if largest_sum >= current_best.1 {
+        return;
+    }
+    let remaining = constants.remaining[index];
+    let lightest = (0..partitions.len())
+        .min_by_key(|&i| partitions[i].sum)
+        .expect("partitions is empty");
+    // Everything left fits in the lightest bin without raising the score, and nothing below
+    // here can score less than the bins already do. Limits might not let it all go there.
+    if !constants.limited() && partitions[lightest].sum + remaining <= largest_sum {
+        let rest = Subset {
+            sum: remaining,
+            mask: (index..elements.len()).fold(0, |mask, i| mask | 1 << i),
+        };
+        let saved_subset = partitions[lightest].clone();
+        partitions[lightest] = Subset::union(&saved_subset, &rest);
+        consider_partitioning(current_best, partitions);
+        partitions[lightest] = saved_subset;
+        return;
+    }
+    // To beat the best, every other bin has to end below it, so the lightest has to end with at
+    // least the rest of the total. If everything left can't get it there, nothing here can win.
+    let others = (constants.n_partitions - T::from(1)) * (current_best.1 - T::from(1));
+    if partitions[lightest].sum + remaining + others < constants.total {
         return;
     }
+    // A state explored before found everything below it that beat the best at the time, and
+    // the best has only improved since.
+    if let Some(ref mut memo) = memo {
+        if memo.seen(index, partitions) {
+            return;
+        }
+    }
+    let capacity = |bin: usize| {
+        constants
+            .capacities
+            .as_ref()
+            .map(|capacities| capacities[bin])
+    };
     let mut ordered_indexed_partition_sums: Vec<(usize, T)> = partitions
         .iter()
         .map(|partition| partition.sum)
         .enumerate()
         .collect();
-    ordered_indexed_partition_sums.sort_by_key(|&(_, sum)| sum);
-    for (i, _) in ordered_indexed_partition_sums {
+    ordered_indexed_partition_sums.sort_by_key(|&(i, sum)| (sum, capacity(i)));
+    // Copies of a value are interchangeable, so each goes in a bin no lower than the one the
+    // copy before it went in, which just decides how many copies each bin gets.
+    let lowest_bin = constants.previous_copies[index].map_or(0, |previous| {
+        partitions
+            .iter()
+            .position(|partition| partition.mask & (1 << previous) > 0)
+            .expect("the previous copy is placed first")
+    });
+    // Elements that can't share a bin make bins with equal sums differ by what's in them.
+    let interchangeable = constants.conflicts.iter().all(|&conflicts| conflicts == 0);
+    let mut tried: Option<(T, Option<T>)> = None;
+    for (i, sum) in ordered_indexed_partition_sums {
+        // Bins with equal sums and capacities, empty ones in particular, lead to the same
+        // subtrees.
+        if i < lowest_bin
+            || (interchangeable && tried == Some((sum, capacity(i))))
+            || !constants.allows(i, &partitions[i], index, elements[index])
+        {
+            continue;
+        }
+        tried = Some((sum, capacity(i)));
         let mut saved_subset = Subset::union(&partitions[i], &Subset::from_index(index, elements));
         mem::swap(&mut saved_subset, &mut partitions[i]);
-        expand_partitions(&elements, index + 1, partitions, current_best, constants);
+        expand_partitions(
+            elements,
+            index + 1,
+            partitions,
+            current_best,
+            constants,
+            memo,
+        );
         partitions[i] = saved_subset;
-        if largest_sum == (*current_best).1 {
+        if largest_sum.max(constants.lower_bound) >= current_best.1 {
             return;
         }
     }
@@ -68,41 +209,173 @@ pub fn find_best_partitioning<T: Arith>(
     elements: &[T],
     n_partitions: u8,
 ) -> (Vec<Subset<T, u64>>, T) {
+    search(elements, n_partitions, None, &Limits::none()).expect("nothing is ruled out")
+}
+
+// The best partitioning that keeps to the limits, if any does.
+pub fn find_best_partitioning_with_limits<T: Arith>(
+    elements: &[T],
+    n_partitions: u8,
+    limits: &Limits<T>,
+) -> Option<(Vec<Subset<T, u64>>, T)> {
+    search(elements, n_partitions, None, limits)
+}
+
+// Skips states reached again by placing elements in a different order. The table is cleared
+// first, so it can be reused across instances to accumulate statistics.
+pub fn find_best_partitioning_with_table<T: Arith>(
+    elements: &[T],
+    n_partitions: u8,
+    table: &mut Table<T>,
+) -> (Vec<Subset<T, u64>>, T) {
+    search(elements, n_partitions, Some(table), &Limits::none()).expect("nothing is ruled out")
+}
+
+// Elements are placed largest first, as in Korf's complete greedy algorithm, so the first
+// solutions found are good ones and the bounds bite early.
+fn search<T: Arith>(
+    elements: &[T],
+    n_partitions: u8,
+    table: Option<&mut Table<T>>,
+    limits: &Limits<T>,
+) -> Option<(Vec<Subset<T, u64>>, T)> {
+    let mut order: Vec<usize> = (0..elements.len()).collect();
+    order.sort_by_key(|&i| Reverse(elements[i]));
+    let sorted: Vec<T> = order.iter().map(|&i| elements[i]).collect();
+    let mut position = vec![0; elements.len()];
+    for (sorted_index, &i) in order.iter().enumerate() {
+        position[i] = sorted_index;
+    }
+    let mut conflicts = vec![0_u64; sorted.len()];
+    for &(a, b) in &limits.apart {
+        conflicts[position[a]] |= 1 << position[b];
+        conflicts[position[b]] |= 1 << position[a];
+    }
     let mut partitions: Vec<Subset<T, u64>> = vec![Subset::empty(); n_partitions as usize];
-    let mut best_partitioning = partitions.clone();
-    best_partitioning[0] = Subset::all(elements);
-    let score = score_partitioning(&best_partitioning);
-    let mut scored_best_partitioning = (best_partitioning, score);
+    let mut remaining = vec![T::from(0); sorted.len() + 1];
+    for i in (0..sorted.len()).rev() {
+        remaining[i] = remaining[i + 1] + sorted[i];
+    }
+    // Copies that can't share a bin with something aren't interchangeable.
+    let previous_copies = if limits.apart.is_empty() {
+        Multiset::new(&sorted).previous_copies()
+    } else {
+        vec![None; sorted.len()]
+    };
     let constants = Constants {
-        total: elements.iter().cloned().sum(),
+        total: remaining[0],
         n_partitions: n_partitions.into(),
+        lower_bound: bounds::lower_bound(&sorted, n_partitions),
+        remaining,
+        previous_copies,
+        capacities: limits.capacities.clone(),
+        conflicts,
+    };
+    // Everything in one bin is the worst partitioning there is. When limits might rule it out,
+    // the search starts from a score one worse, which any partitioning they allow beats.
+    let mut best_partitioning = partitions.clone();
+    best_partitioning[0] = Subset::all(&sorted);
+    let unreachable = constants.total + T::from(1);
+    let score = if constants.limited() {
+        unreachable
+    } else {
+        score_partitioning(&best_partitioning)
     };
+    let mut scored_best_partitioning = (best_partitioning, score);
+    let mut memo = table.map(|table| {
+        table.clear();
+        Memo::new(table, &constants.previous_copies)
+    });
     expand_partitions(
-        elements,
+        &sorted,
         0,
         partitions.as_mut_slice(),
         &mut scored_best_partitioning,
-        constants,
+        &constants,
+        &mut memo,
     );
-    scored_best_partitioning
+    let (partitions, score) = scored_best_partitioning;
+    if constants.limited() && score == unreachable {
+        return None;
+    }
+    let partitions = partitions
+        .iter()
+        .map(|partition| {
+            let mask = bits(partition.mask).fold(0, |mask, bit| {
+                mask | 1 << order[bit.trailing_zeros() as usize]
+            });
+            Subset {
+                sum: partition.sum,
+                mask,
+            }
+        })
+        .collect();
+    Some((partitions, score))
 }
 
 #[cfg(test)]
 mod tests {
-    extern crate test;
-    use self::test::Bencher;
-    use benchmark_data;
-    use gcc::find_best_partitioning;
+    use crate::gcc::{find_best_partitioning, find_best_partitioning_with_table};
+    use crate::select::{compare_partitioning_methods, PartitionMethod};
+    use crate::stats;
+    use crate::transposition::Table;
     use proptest::collection::vec;
-    use select::{compare_partitioning_methods, PartitionMethod};
-    #[bench]
-    fn bench_gcc(b: &mut Bencher) {
-        b.iter(|| find_best_partitioning(&benchmark_data::SMALL_ELEMENTS, 4));
-    }
+    use proptest::proptest;
     proptest! {
         #[test]
         fn prop_gcc_brute(ref elements in vec(1_i32..1000, 1..10)) {
-            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::GCC, &elements, 4);
+            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::GCC, elements, 4);
        }
     }
+    proptest! {
+        #[test]
+        fn prop_gcc_brute_duplicates(ref elements in vec(1_i32..5, 1..10), n in 2_u8..5) {
+            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::GCC, elements, n);
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_gcc_table(ref elements in vec(1_i32..20, 1..10), n in 2_u8..5, bytes in 0_usize..4096) {
+            let mut table = Table::with_memory(bytes);
+            let (partitions, score) = find_best_partitioning_with_table(elements, n, &mut table);
+            assert_eq!(score, find_best_partitioning(elements, n).1);
+            assert_eq!(partitions.iter().map(|subset| subset.sum).max(), Some(score));
+            assert_eq!(partitions.iter().fold(0, |mask, subset| mask | subset.mask), (1 << elements.len()) - 1);
+       }
+    }
+    #[test]
+    fn unit_gcc_table() {
+        // Different placements reach the same sums, such as 89 + 71 and 97 + 63 in one bin.
+        let elements = [97, 89, 83, 79, 73, 71, 63, 61, 59, 53, 47, 43, 41, 37];
+        let mut table = Table::with_memory(1 << 16);
+        stats::take_nodes();
+        let (_, score) = find_best_partitioning_with_table(&elements, 3, &mut table);
+        let with_table = stats::take_nodes();
+        assert_eq!(score, find_best_partitioning(&elements, 3).1);
+        assert!(with_table < stats::take_nodes());
+        assert!(table.stats().hit_rate() > 0.0);
+    }
+    #[test]
+    fn unit_gcc_perfect() {
+        let elements = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        stats::take_nodes();
+        let (partitions, score) = find_best_partitioning(&elements, 3);
+        let nodes = stats::take_nodes();
+        assert_eq!(score, 15);
+        // The search stops at the first perfect partitioning it finds.
+        assert!(nodes < 20);
+        assert!(partitions.iter().all(|subset| subset.sum == 15));
+    }
+    #[test]
+    fn unit_gcc_copies() {
+        // An odd number of copies can't be split evenly, so the search can't stop early and has
+        // to see every way of dividing them between the bins.
+        let elements = [7; 11];
+        stats::take_nodes();
+        let (partitions, score) = find_best_partitioning(&elements, 3);
+        assert_eq!(score, 28);
+        assert_eq!(partitions.len(), 3);
+        // Treating the copies as distinct takes over 15000 nodes.
+        assert!(stats::take_nodes() < 200);
+    }
 }
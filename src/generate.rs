@@ -0,0 +1,92 @@
+use crate::ess::count_perfect_partitions;
+use crate::rng::XorShift;
+use std::f64::consts::PI;
+
+// Random instance families from the number partitioning literature. All values are positive
+// and every generator is reproducible from its seed.
+
+// Uniform integers in 1..=2^bits - 1, the standard model where `bits` sets the precision.
+pub fn uniform(count: usize, bits: u32, seed: u64) -> Vec<i64> {
+    assert!((1..=62).contains(&bits), "bits must be between 1 and 62");
+    let mut rng = XorShift::new(seed);
+    let max = (1_u64 << bits) - 1;
+    (0..count)
+        .map(|_| (rng.next_u64() % max + 1) as i64)
+        .collect()
+}
+
+// Normally distributed integers, truncated to stay at least 1.
+pub fn normal(count: usize, mean: f64, std_dev: f64, seed: u64) -> Vec<i64> {
+    let mut rng = XorShift::new(seed);
+    (0..count)
+        .map(|_| {
+            // Box-Muller. 1 - unit() is in (0, 1], so the log is finite.
+            let radius = (-2.0 * (1.0 - rng.unit()).ln()).sqrt();
+            let z = radius * (2.0 * PI * rng.unit()).cos();
+            (mean + std_dev * z).round().max(1.0) as i64
+        })
+        .collect()
+}
+
+// Precision at which perfect partitions stop being likely. For two bins this is Mertens'
+// kappa_c = 1 - log2(count) / (2 count); for more bins we use the usual generalization
+// kappa_c = log2(n) / (n - 1), which is the same to first order.
+pub fn critical_bits(count: usize, n: u8) -> u32 {
+    let count = count as f64;
+    let n = f64::from(n);
+    let bits = count * n.log2() / (n - 1.0) - count.log2() / 2.0;
+    bits.round().clamp(1.0, 62.0) as u32
+}
+
+// Uniform instances at the phase transition, where the exact solvers find them hardest.
+pub fn near_perfect(count: usize, n: u8, seed: u64) -> Vec<i64> {
+    uniform(count, critical_bits(count, n), seed)
+}
+
+// The fraction of `samples` uniform instances with at least one perfect two-way partition, for
+// locating the phase transition empirically. Instance i uses seed + i.
+pub fn perfect_probability(count: usize, bits: u32, samples: usize, seed: u64) -> f64 {
+    if samples == 0 {
+        return 0.0;
+    }
+    let perfect = (0..samples as u64)
+        .filter(|&i| count_perfect_partitions(&uniform(count, bits, seed.wrapping_add(i))) > 0)
+        .count();
+    perfect as f64 / samples as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{critical_bits, near_perfect, normal, perfect_probability, uniform};
+
+    #[test]
+    fn unit_uniform() {
+        let elements = uniform(1000, 4, 3);
+        assert_eq!(elements.len(), 1000);
+        assert!(elements.iter().all(|x| (1..16).contains(x)));
+        assert_eq!(elements, uniform(1000, 4, 3));
+        assert_ne!(elements, uniform(1000, 4, 4));
+    }
+    #[test]
+    fn unit_normal() {
+        let elements = normal(10_000, 1000.0, 100.0, 1);
+        let mean = elements.iter().sum::<i64>() as f64 / elements.len() as f64;
+        assert!((mean - 1000.0).abs() < 10.0);
+    }
+    #[test]
+    fn unit_near_perfect() {
+        assert_eq!(critical_bits(30, 2), 28);
+        let elements = near_perfect(30, 2, 0);
+        assert!(elements.iter().all(|&x| x < 1 << 28));
+    }
+    #[test]
+    fn unit_perfect_probability() {
+        // With one bit every element is 1, so the sides always differ by at most one.
+        assert_eq!(perfect_probability(10, 1, 5, 0), 1.0);
+        assert_eq!(perfect_probability(11, 1, 5, 0), 1.0);
+        // Far above the transition there's almost never a perfect partition.
+        assert_eq!(perfect_probability(10, 40, 20, 0), 0.0);
+        let middle = perfect_probability(16, critical_bits(16, 2), 50, 0);
+        assert!(middle > 0.0 && middle < 1.0);
+    }
+}
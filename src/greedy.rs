@@ -0,0 +1,214 @@
+use crate::arith::Arith;
+use crate::stats;
+use crate::subset::Subset;
+use std::cmp::Reverse;
+
+pub const MULTIFIT_ITERATIONS: u32 = 7;
+
+fn sorted_descending<T: Arith>(elements: &[T]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..elements.len()).collect();
+    order.sort_by_key(|&i| Reverse(elements[i]));
+    order
+}
+
+fn finish<T: Arith>(mut partitions: Vec<Subset<T, u64>>) -> Vec<Subset<T, u64>> {
+    partitions.sort_by_key(|subset| Reverse(subset.sum));
+    partitions
+}
+
+fn assign_to_lightest<T: Arith, I: Iterator<Item = usize>>(
+    order: I,
+    elements: &[T],
+    n: u8,
+) -> Vec<Subset<T, u64>> {
+    let mut partitions = vec![Subset::empty(); n as usize];
+    for i in order {
+        let lightest = partitions
+            .iter_mut()
+            .min_by_key(|subset| subset.sum)
+            .expect("partitions is empty");
+        *lightest = Subset::union(lightest, &Subset::from_index(i, elements));
+    }
+    finish(partitions)
+}
+
+pub fn greedy<T: Arith>(elements: &[T], n: u8) -> Vec<Subset<T, u64>> {
+    assign_to_lightest(0..elements.len(), elements, n)
+}
+
+pub fn lpt<T: Arith>(elements: &[T], n: u8) -> Vec<Subset<T, u64>> {
+    assign_to_lightest(sorted_descending(elements).into_iter(), elements, n)
+}
+
+fn first_fit_decreasing<T: Arith>(
+    order: &[usize],
+    elements: &[T],
+    n: u8,
+    capacity: T,
+) -> Option<Vec<Subset<T, u64>>> {
+    let mut partitions = vec![Subset::empty(); n as usize];
+    for &i in order {
+        let bin = partitions
+            .iter_mut()
+            .find(|subset| subset.sum + elements[i] <= capacity)?;
+        *bin = Subset::union(bin, &Subset::from_index(i, elements));
+    }
+    Some(partitions)
+}
+
+// Binary search on the bin capacity, packing with first-fit-decreasing at each step.
+pub fn multifit<T: Arith>(elements: &[T], n: u8, iterations: u32) -> Vec<Subset<T, u64>> {
+    if n == 0 {
+        // No capacity to search for; fail or succeed the same way as the other methods.
+        return lpt(elements, n);
+    }
+    let order = sorted_descending(elements);
+    let total: T = elements.iter().cloned().sum();
+    let largest = order.first().map_or(T::from(0), |&i| elements[i]);
+    let mut lower = (total + T::from(n - 1)) / n.into();
+    let mut upper = T::from(2) * lower;
+    lower = lower.max(largest);
+    upper = upper.max(largest);
+    let mut best = None;
+    for _ in 0..iterations {
+        if lower >= upper {
+            break;
+        }
+        let capacity = (lower + upper) / T::from(2);
+        match first_fit_decreasing(&order, elements, n, capacity) {
+            Some(partitions) => {
+                upper = capacity;
+                best = Some(partitions);
+            }
+            None => lower = capacity + T::from(1),
+        }
+    }
+    match best.or_else(|| first_fit_decreasing(&order, elements, n, upper)) {
+        Some(partitions) => finish(partitions),
+        None => lpt(elements, n),
+    }
+}
+
+struct CompleteGreedy<'a, T> {
+    elements: &'a [T],
+    order: Vec<usize>,
+    nodes_remaining: u64,
+    best: Vec<Subset<T, u64>>,
+    best_score: T,
+}
+
+impl<'a, T: Arith> CompleteGreedy<'a, T> {
+    fn expand(&mut self, depth: usize, partitions: &mut [Subset<T, u64>]) {
+        stats::count_node();
+        let largest_sum = partitions.iter().map(|subset| subset.sum).max().unwrap();
+        if largest_sum >= self.best_score {
+            return;
+        }
+        if depth == self.order.len() {
+            self.best_score = largest_sum;
+            self.best.clone_from_slice(partitions);
+            return;
+        }
+        let element = Subset::from_index(self.order[depth], self.elements);
+        let mut bins: Vec<usize> = (0..partitions.len()).collect();
+        bins.sort_by_key(|&bin| partitions[bin].sum);
+        for bin in bins {
+            if self.nodes_remaining == 0 {
+                return;
+            }
+            self.nodes_remaining -= 1;
+            let saved = partitions[bin].clone();
+            partitions[bin] = Subset::union(&saved, &element);
+            self.expand(depth + 1, partitions);
+            partitions[bin] = saved;
+        }
+    }
+}
+
+// The Complete Greedy Algorithm's search tree, explored depth first with the greedy
+// assignment first, stopping after node_limit nodes. The first leaf reached is the LPT
+// solution, so the result is never worse than lpt.
+pub fn complete<T: Arith>(elements: &[T], n: u8, node_limit: u64) -> Vec<Subset<T, u64>> {
+    let best = lpt(elements, n);
+    let best_score = best[0].sum;
+    let mut search = CompleteGreedy {
+        elements,
+        order: sorted_descending(elements),
+        nodes_remaining: node_limit,
+        best,
+        best_score,
+    };
+    let mut partitions = vec![Subset::empty(); n as usize];
+    search.expand(0, &mut partitions);
+    finish(search.best)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::greedy::{complete, lpt, multifit, MULTIFIT_ITERATIONS};
+    use crate::select::{compare_partitioning_methods, partition_using, PartitionMethod};
+    use crate::subset::Subset;
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn assert_valid(partitions: &[Subset<i32, u64>], elements: &[i32], n: u8) {
+        assert_eq!(partitions.len(), n as usize);
+        let union = partitions
+            .iter()
+            .fold(Subset::empty(), |acc, subset| Subset::union(&acc, subset));
+        assert_eq!(union, Subset::all(elements));
+    }
+    fn score(partitions: &[Subset<i32, u64>]) -> i32 {
+        partitions.iter().map(|subset| subset.sum).max().unwrap()
+    }
+    proptest! {
+        #[test]
+        fn prop_lpt_bound(ref elements in vec(1_i32..1000, 1..10), n in 2_u8..5) {
+            let partitions = lpt(elements, n);
+            assert_valid(&partitions, elements, n);
+            let optimal = score(&partition_using(PartitionMethod::GCC, elements, n).to_subsets(elements));
+            let n = i32::from(n);
+            assert!(partitions[0].sum * 3 * n <= optimal * (4 * n - 1));
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_multifit_bound(ref elements in vec(1_i32..1000, 1..10), n in 2_u8..5) {
+            let partitions = multifit(elements, n, MULTIFIT_ITERATIONS);
+            assert_valid(&partitions, elements, n);
+            let optimal = score(&partition_using(PartitionMethod::GCC, elements, n).to_subsets(elements));
+            assert!(partitions[0].sum <= optimal * 5 / 4 + 1);
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_greedy_valid(ref elements in vec(1_i32..1000, 1..10), n in 2_u8..5) {
+            let partitions = partition_using(PartitionMethod::Greedy, elements, n).to_subsets(elements);
+            assert_valid(&partitions, elements, n);
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_complete_greedy_gcc(ref elements in vec(1_i32..1000, 1..10)) {
+            compare_partitioning_methods(
+                PartitionMethod::GCC,
+                PartitionMethod::CompleteGreedy { node_limit: u64::MAX },
+                elements,
+                4,
+            );
+       }
+    }
+    #[test]
+    fn unit_complete_greedy_no_nodes() {
+        let elements = [8, 7, 6, 5, 4];
+        assert_eq!(complete(&elements, 2, 0), lpt(&elements, 2));
+    }
+    #[test]
+    fn unit_multifit_small() {
+        let elements = [1, 2];
+        let partitions = multifit(&elements, 2, MULTIFIT_ITERATIONS);
+        assert_valid(&partitions, &elements, 2);
+        assert_eq!(partitions[0].sum, 2);
+        assert_eq!(multifit(&[] as &[i32], 0, MULTIFIT_ITERATIONS), vec![]);
+    }
+}
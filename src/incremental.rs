@@ -0,0 +1,246 @@
+use crate::arith::Arith;
+use crate::local_search::{bits, element, exchange};
+use crate::subset::Subset;
+use std::cmp::Reverse;
+
+// Repartitioning after a few elements come and go, keeping the rest where they were as far as
+// possible. Bins keep their identity, so unlike the solvers the result isn't sorted by sum.
+
+#[derive(Debug, Clone)]
+pub struct Change<T> {
+    pub added: Vec<T>,
+    // Indices into the previous elements.
+    pub removed: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Budget<T> {
+    // At most this many of the kept elements may end up outside their previous bin.
+    Moves(usize),
+    // Each kept element outside its previous bin costs this much. An exchange is only made if
+    // it lowers the heavier of its two bins by more than the moves it adds cost.
+    Weighted { move_cost: T },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repartitioning<T> {
+    // The kept elements in their previous order, followed by the added ones.
+    pub elements: Vec<T>,
+    pub partitions: Vec<Subset<T, u64>>,
+    // How many kept elements changed bins.
+    pub moves: usize,
+}
+
+// Change in the number of displaced elements from moving `bit` between bins.
+fn displacement(homes: &[Option<usize>], bit: u64, from: usize, to: usize) -> isize {
+    match homes[bit.trailing_zeros() as usize] {
+        Some(home) if home == from => 1,
+        Some(home) if home == to => -1,
+        _ => 0,
+    }
+}
+
+fn allowed<T: Arith>(budget: Budget<T>, moves: usize, gain: T, displacement: isize) -> bool {
+    match budget {
+        Budget::Moves(limit) => moves as isize + displacement <= limit as isize,
+        Budget::Weighted { move_cost } => {
+            displacement <= 0 || gain > move_cost * T::from(displacement as u8)
+        }
+    }
+}
+
+// Like local_search::improve, but only makes exchanges the budget allows, preferring the one
+// that leaves the lower larger sum and then the one that displaces fewer elements.
+fn improve<T: Arith>(
+    elements: &[T],
+    homes: &[Option<usize>],
+    partitions: &mut [Subset<T, u64>],
+    budget: Budget<T>,
+) -> usize {
+    let mut moves = 0;
+    loop {
+        let heavy = (0..partitions.len())
+            .max_by_key(|&bin| partitions[bin].sum)
+            .expect("partitions is empty");
+        let mut best: Option<(usize, u64, u64, T, isize)> = None;
+        for light in (0..partitions.len()).filter(|&bin| bin != heavy) {
+            let gap = partitions[heavy].sum - partitions[light].sum;
+            for give in bits(partitions[heavy].mask) {
+                let x = element(give, elements);
+                let from_home = displacement(homes, give, heavy, light);
+                for take in Some(0).into_iter().chain(bits(partitions[light].mask)) {
+                    let (delta, displaced) = if take == 0 {
+                        (x, from_home)
+                    } else {
+                        let y = element(take, elements);
+                        (x - y, from_home + displacement(homes, take, light, heavy))
+                    };
+                    if delta <= T::from(0) || delta >= gap {
+                        continue;
+                    }
+                    let new_max =
+                        (partitions[heavy].sum - delta).max(partitions[light].sum + delta);
+                    if !allowed(budget, moves, partitions[heavy].sum - new_max, displaced) {
+                        continue;
+                    }
+                    let better = best.as_ref().is_none_or(|&(_, _, _, max, best_displaced)| {
+                        (new_max, displaced) < (max, best_displaced)
+                    });
+                    if better {
+                        best = Some((light, give, take, new_max, displaced));
+                    }
+                }
+            }
+        }
+        let (light, give, take, _, displaced) = match best {
+            Some(best) => best,
+            None => return moves,
+        };
+        let mut heavy_bin = partitions[heavy].clone();
+        let mut light_bin = partitions[light].clone();
+        exchange(&mut heavy_bin, &mut light_bin, give, take, elements);
+        partitions[heavy] = heavy_bin;
+        partitions[light] = light_bin;
+        moves = (moves as isize + displaced) as usize;
+    }
+}
+
+// Drops the removed elements, places the added ones largest first into the lightest bin, then
+// improves the result by moving and swapping elements within the budget.
+pub fn repartition<T: Arith>(
+    elements: &[T],
+    prior: &[Subset<T, u64>],
+    change: &Change<T>,
+    budget: Budget<T>,
+) -> Repartitioning<T> {
+    let mut new_index = vec![None; elements.len()];
+    let mut kept = Vec::with_capacity(elements.len() + change.added.len());
+    for i in 0..elements.len() {
+        if !change.removed.contains(&i) {
+            new_index[i] = Some(kept.len());
+            kept.push(elements[i]);
+        }
+    }
+    let mut homes = vec![None; kept.len()];
+    let mut masks = vec![0_u64; prior.len()];
+    for (bin, subset) in prior.iter().enumerate() {
+        for bit in bits(subset.mask) {
+            if let Some(i) = new_index[bit.trailing_zeros() as usize] {
+                homes[i] = Some(bin);
+                masks[bin] |= 1 << i;
+            }
+        }
+    }
+    let mut added: Vec<usize> = (kept.len()..kept.len() + change.added.len()).collect();
+    kept.extend_from_slice(&change.added);
+    homes.resize(kept.len(), None);
+    let elements = kept;
+    let mut partitions: Vec<Subset<T, u64>> = masks
+        .into_iter()
+        .map(|mask| Subset::new(mask, &elements))
+        .collect();
+    added.sort_by_key(|&i| Reverse(elements[i]));
+    for i in added {
+        let lightest = partitions
+            .iter_mut()
+            .min_by_key(|subset| subset.sum)
+            .expect("partitions is empty");
+        *lightest = Subset::union(lightest, &Subset::from_index(i, &elements));
+    }
+    let moves = improve(&elements, &homes, &mut partitions, budget);
+    Repartitioning {
+        elements,
+        partitions,
+        moves,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::incremental::{repartition, Budget, Change};
+    use crate::select::{partition_using, PartitionMethod};
+    use crate::subset::Subset;
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn moved(prior: &[Subset<i32, u64>], partitions: &[Subset<i32, u64>], kept: usize) -> usize {
+        (0..kept)
+            .filter(|&i| {
+                let bin = |partitions: &[Subset<i32, u64>]| {
+                    partitions
+                        .iter()
+                        .position(|subset| subset.mask & (1 << i) > 0)
+                };
+                bin(prior) != bin(partitions)
+            })
+            .count()
+    }
+    proptest! {
+        #[test]
+        fn prop_repartition_budget(
+            ref elements in vec(1_i32..1000, 1..12),
+            ref added in vec(1_i32..1000, 0..4),
+            n in 2_u8..5,
+            budget in 0_usize..4,
+        ) {
+            let prior = partition_using(PartitionMethod::KK, elements, n).to_subsets(elements);
+            let change = Change { added: added.clone(), removed: vec![] };
+            let result = repartition(elements, &prior, &change, Budget::Moves(budget));
+            assert!(result.moves <= budget);
+            assert_eq!(result.moves, moved(&prior, &result.partitions, elements.len()));
+            let mut union = Subset::empty();
+            for subset in &result.partitions {
+                assert_eq!(union.mask & subset.mask, 0);
+                assert_eq!(*subset, Subset::new(subset.mask, &result.elements));
+                union = Subset::union(&union, subset);
+            }
+            assert_eq!(union, Subset::all(&result.elements));
+        }
+    }
+    #[test]
+    fn unit_repartition_removed() {
+        let elements = [5, 4, 3, 3, 3];
+        let prior = vec![
+            Subset::new(0b00011, &elements),
+            Subset::new(0b11100, &elements),
+        ];
+        let change = Change {
+            added: vec![6],
+            removed: vec![0],
+        };
+        let result = repartition(&elements, &prior, &change, Budget::Moves(0));
+        assert_eq!(result.elements, vec![4, 3, 3, 3, 6]);
+        assert_eq!(result.moves, 0);
+        let sums: Vec<i32> = result.partitions.iter().map(|subset| subset.sum).collect();
+        assert_eq!(sums, vec![10, 9]);
+    }
+    #[test]
+    fn unit_repartition_weighted() {
+        let elements = [4, 4, 2, 2];
+        let prior = vec![
+            Subset::new(0b0011, &elements),
+            Subset::new(0b1100, &elements),
+        ];
+        let change = Change {
+            added: vec![],
+            removed: vec![],
+        };
+        // Swapping a 4 and a 2 evens the bins at 6, a gain of 2 for two moves.
+        let cheap = repartition(
+            &elements,
+            &prior,
+            &change,
+            Budget::Weighted { move_cost: 0 },
+        );
+        assert_eq!(cheap.moves, 2);
+        assert_eq!(cheap.partitions[0].sum, 6);
+        let expensive = repartition(
+            &elements,
+            &prior,
+            &change,
+            Budget::Weighted { move_cost: 1 },
+        );
+        assert_eq!(expensive.moves, 0);
+        assert_eq!(expensive.partitions[0].sum, 8);
+    }
+}
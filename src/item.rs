@@ -0,0 +1,62 @@
+use crate::arith::Arith;
+use crate::subset::Subset;
+
+// An element with an identity attached, for callers whose inputs are records rather than bare
+// numbers. Solvers still work on the weights; the labels are looked back up through the masks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item<T, L> {
+    pub label: L,
+    pub weight: T,
+}
+
+impl<T: Arith, L> Item<T, L> {
+    pub fn new(label: L, weight: T) -> Self {
+        Self { label, weight }
+    }
+}
+
+pub fn weights<T: Arith, L>(items: &[Item<T, L>]) -> Vec<T> {
+    items.iter().map(|item| item.weight).collect()
+}
+
+pub fn labels<'a, T: Arith, L>(subset: &Subset<T, u64>, items: &'a [Item<T, L>]) -> Vec<&'a L> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| subset.mask & (1 << i) > 0)
+        .map(|(_, item)| &item.label)
+        .collect()
+}
+
+pub fn label_partitions<'a, T: Arith, L>(
+    partitions: &[Subset<T, u64>],
+    items: &'a [Item<T, L>],
+) -> Vec<Vec<&'a L>> {
+    partitions
+        .iter()
+        .map(|subset| labels(subset, items))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::item::{label_partitions, weights, Item};
+    use crate::select::{partition_using, PartitionMethod};
+
+    #[test]
+    fn unit_label_partitions() {
+        let items = vec![
+            Item::new("job-1", 5),
+            Item::new("job-2", 4),
+            Item::new("job-3", 3),
+            Item::new("job-4", 3),
+            Item::new("job-5", 3),
+        ];
+        let elements = weights(&items);
+        let partitions = partition_using(PartitionMethod::GCC, &elements, 2).to_subsets(&elements);
+        let mut labels = label_partitions(&partitions, &items);
+        labels.sort();
+        let expected = vec![vec![&"job-1", &"job-2"], vec![&"job-3", &"job-4", &"job-5"]];
+        assert_eq!(labels, expected);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::arith::Arith;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+// Karmarkar-Karp for inputs too large for Subset masks. Rather than carrying a full
+// partitioning per heap entry, these track which elements end up together with index lists,
+// so memory stays linear in the number of elements, times the number of bins for n_kk, and
+// two-way KK runs in O(N log N).
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Difference<T> {
+    value: T,
+    index: usize,
+}
+
+// Two-way KK. Each differencing step puts the smaller entry's representative on the opposite
+// side from the larger one's, which forms a tree over the elements: a child is always attached
+// before its parent, so walking the edges backwards colours every parent before its children.
+// Returns the side (0 or 1) of each element, with side 0 the larger sum.
+pub fn kk<T: Arith>(elements: &[T]) -> Vec<usize> {
+    let mut heap: BinaryHeap<Difference<T>> = elements
+        .iter()
+        .enumerate()
+        .map(|(index, &value)| Difference { value, index })
+        .collect();
+    let mut edges = Vec::with_capacity(elements.len());
+    while heap.len() > 1 {
+        let first = heap.pop().expect("heap is empty");
+        let snd = heap.pop().expect("heap is empty");
+        edges.push((snd.index, first.index));
+        heap.push(Difference {
+            value: first.value - snd.value,
+            index: first.index,
+        });
+    }
+    let mut sides = vec![0; elements.len()];
+    for &(child, parent) in edges.iter().rev() {
+        sides[child] = 1 - sides[parent];
+    }
+    sides
+}
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Bin<T> {
+    sum: T,
+    head: usize,
+    tail: usize,
+}
+
+// A single element stays as just its index until it's merged into something, and only then
+// gets its n bins. Every entry with bins holds at least two elements, so at most N/2 of them are
+// in the heap at once: at most N n / 2 bins, rather than the N n of giving every element its
+// bins up front. That's still O(N n) when elements pair off before any merges further.
+#[derive(Debug)]
+enum Contents<T> {
+    Single(usize),
+    Bins(Vec<Bin<T>>),
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    delta: T,
+    contents: Contents<T>,
+}
+
+impl<T: Arith> PartialEq for Node<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.delta == other.delta
+    }
+}
+impl<T: Arith> Eq for Node<T> {}
+impl<T: Arith> PartialOrd for Node<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T: Arith> Ord for Node<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.delta.cmp(&other.delta)
+    }
+}
+
+fn single<T: Arith>(i: usize, x: T) -> Bin<T> {
+    Bin {
+        sum: x,
+        head: i,
+        tail: i,
+    }
+}
+
+// Element lists are singly linked through `next`, so concatenating two bins is O(1).
+fn concatenate<T: Arith>(next: &mut [usize], left: Bin<T>, right: Bin<T>) -> Bin<T> {
+    if left.head == NIL {
+        return right;
+    }
+    if right.head != NIL {
+        next[left.tail] = right.head;
+    }
+    Bin {
+        sum: left.sum + right.sum,
+        head: left.head,
+        tail: if right.head == NIL {
+            left.tail
+        } else {
+            right.tail
+        },
+    }
+}
+
+// Multi-way KK: the same merge as ckk::n_kk, pairing the largest bin of one entry with the
+// smallest of the other. Returns the bin of each element, with bins numbered in descending
+// order of their sums.
+pub fn n_kk<T: Arith>(elements: &[T], n: u8) -> Vec<usize> {
+    let n = n as usize;
+    let mut next = vec![NIL; elements.len()];
+    let empty = Bin {
+        sum: T::from(0),
+        head: NIL,
+        tail: NIL,
+    };
+    let expand = |contents: Contents<T>| match contents {
+        Contents::Single(i) => {
+            let mut bins = vec![empty; n];
+            bins[0] = single(i, elements[i]);
+            bins
+        }
+        Contents::Bins(bins) => bins,
+    };
+    let mut heap: BinaryHeap<Node<T>> = elements
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| Node {
+            delta: if n > 1 { x } else { T::from(0) },
+            contents: Contents::Single(i),
+        })
+        .collect();
+    let mut result = vec![empty; n];
+    while let Some(first) = heap.pop() {
+        let mut bins = expand(first.contents);
+        match heap.pop() {
+            None => {
+                result = bins;
+                break;
+            }
+            // A single element only adds to the smallest bin.
+            Some(Node {
+                contents: Contents::Single(i),
+                ..
+            }) => {
+                bins[n - 1] = concatenate(&mut next, bins[n - 1], single(i, elements[i]));
+            }
+            Some(Node {
+                contents: Contents::Bins(other),
+                ..
+            }) => {
+                for (bin, &other) in bins.iter_mut().zip(other.iter().rev()) {
+                    *bin = concatenate(&mut next, *bin, other);
+                }
+            }
+        }
+        bins.sort_unstable_by_key(|bin| Reverse(bin.sum));
+        heap.push(Node {
+            delta: bins[0].sum - bins[n - 1].sum,
+            contents: Contents::Bins(bins),
+        });
+    }
+    let mut assignment = vec![0; elements.len()];
+    for (bin, contents) in result.iter().enumerate() {
+        let mut i = contents.head;
+        while i != NIL {
+            assignment[i] = bin;
+            i = next[i];
+        }
+    }
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ckk;
+    use crate::large_kk::{kk, n_kk};
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn sums(elements: &[i64], n: usize, assignment: &[usize]) -> Vec<i64> {
+        let mut sums = vec![0; n];
+        for (x, &bin) in elements.iter().zip(assignment) {
+            sums[bin] += x;
+        }
+        sums
+    }
+    proptest! {
+        #[test]
+        fn prop_kk_matches_ckk_kk(ref elements in vec(1_i64..1000, 1..40)) {
+            let sides = kk(elements);
+            let sums = sums(elements, 2, &sides);
+            assert!(sums[0] >= sums[1]);
+            assert_eq!(sums[0] - sums[1], ckk::kk(elements).delta());
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_n_kk_matches_kk(ref elements in vec(1_i64..1000, 1..40)) {
+            let two_way = sums(elements, 2, &n_kk(elements, 2));
+            assert_eq!(two_way[0] - two_way[1], ckk::kk(elements).delta());
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_n_kk_sorted(ref elements in vec(1_i64..1000, 1..40), n in 2_u8..8) {
+            let assignment = n_kk(elements, n);
+            let sums = sums(elements, n as usize, &assignment);
+            assert!(sums.windows(2).all(|pair| pair[0] >= pair[1]));
+            assert_eq!(sums.iter().sum::<i64>(), elements.iter().sum::<i64>());
+       }
+    }
+    #[test]
+    fn unit_n_kk_large() {
+        let elements: Vec<i64> = (0..200_000).map(|i| (i * 7919) % 1_000_003 + 1).collect();
+        let assignment = n_kk(&elements, 4);
+        let sums = sums(&elements, 4, &assignment);
+        assert!(sums[0] - sums[3] < 1_000_003);
+    }
+}
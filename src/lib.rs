@@ -1,7 +1,3 @@
-#![feature(nll)]
-#![feature(test)]
-#![feature(range_contains)]
-#![feature(range_is_empty)]
 #![allow(unknown_lints)]
 #![warn(
     clippy::all,
@@ -9,21 +5,35 @@
     clippy::unseparated_literal_suffix
 )]
 
-extern crate num;
-#[cfg(test)]
-#[macro_use]
-extern crate proptest;
-extern crate itertools;
-extern crate structopt;
-
+pub mod anneal;
 mod arith;
 #[cfg(test)]
 mod benchmark_data;
+pub mod bounds;
 mod brute;
 pub mod ckk;
 pub mod ess;
 pub mod gcc;
+pub mod generate;
+pub mod greedy;
+pub mod incremental;
+pub mod item;
+pub mod large_kk;
+pub mod local_search;
+pub mod milp;
+pub mod multiset;
+pub mod online;
+mod rng;
 pub mod rnp;
+pub mod run;
 pub mod select;
 pub mod snp;
+pub mod solution;
+pub mod solver;
+pub mod ss;
+pub mod stats;
 pub mod subset;
+pub mod subset_sum;
+pub mod transposition;
+pub mod vector;
+pub mod verify;
@@ -0,0 +1,138 @@
+use crate::arith::Arith;
+use crate::subset::Subset;
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+pub(crate) fn bits(mask: u64) -> impl Iterator<Item = u64> {
+    (0..64)
+        .map(|i| 1_u64 << i)
+        .filter(move |bit| mask & bit > 0)
+}
+
+pub(crate) fn element<T: Arith>(bit: u64, elements: &[T]) -> T {
+    elements[bit.trailing_zeros() as usize]
+}
+
+// Moves `give` from heavy to light and `take` from light to heavy. Either mask may be 0.
+pub(crate) fn exchange<T: Arith>(
+    heavy: &mut Subset<T, u64>,
+    light: &mut Subset<T, u64>,
+    give: u64,
+    take: u64,
+    elements: &[T],
+) {
+    let delta = Subset::new(give, elements).sum - Subset::new(take, elements).sum;
+    heavy.mask ^= give | take;
+    heavy.sum -= delta;
+    light.mask ^= give | take;
+    light.sum += delta;
+}
+
+// Finds the move or swap between the heaviest bin and `light` that most reduces the larger of
+// the two sums. Returns the masks to exchange and the resulting larger sum.
+fn best_exchange<T: Arith>(
+    heavy: &Subset<T, u64>,
+    light: &Subset<T, u64>,
+    elements: &[T],
+) -> Option<(u64, u64, T)> {
+    let gap = heavy.sum - light.sum;
+    let mut best: Option<(u64, u64, T)> = None;
+    for give in bits(heavy.mask) {
+        let x = element(give, elements);
+        let takes = Some(0).into_iter().chain(bits(light.mask));
+        for take in takes {
+            let delta = if take == 0 {
+                x
+            } else {
+                x - element(take, elements)
+            };
+            if delta <= T::from(0) || delta >= gap {
+                continue;
+            }
+            let new_max = (heavy.sum - delta).max(light.sum + delta);
+            if best.as_ref().is_none_or(|&(_, _, max)| new_max < max) {
+                best = Some((give, take, new_max));
+            }
+        }
+    }
+    best
+}
+
+// Repeatedly applies the best single move or pairwise swap out of the heaviest bin until no
+// exchange lowers it, or until the time limit runs out.
+pub fn improve<T: Arith>(
+    elements: &[T],
+    mut partitions: Vec<Subset<T, u64>>,
+    time_limit: Option<Duration>,
+) -> Vec<Subset<T, u64>> {
+    let start = Instant::now();
+    loop {
+        if time_limit.is_some_and(|limit| start.elapsed() >= limit) {
+            break;
+        }
+        partitions.sort_by_key(|subset| Reverse(subset.sum));
+        let (heaviest, rest) = match partitions.split_first_mut() {
+            Some(split) => split,
+            None => break,
+        };
+        let best = rest
+            .iter_mut()
+            .filter_map(|light| {
+                let (give, take, max) = best_exchange(heaviest, light, elements)?;
+                Some((light, give, take, max))
+            })
+            .min_by_key(|&(_, _, _, max)| max);
+        match best {
+            Some((light, give, take, _)) => exchange(heaviest, light, give, take, elements),
+            None => break,
+        }
+    }
+    partitions.sort_by_key(|subset| Reverse(subset.sum));
+    partitions
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ckk::n_kk;
+    use crate::local_search::improve;
+    use crate::subset::Subset;
+    use proptest::collection::vec;
+    use proptest::proptest;
+    use std::time::Duration;
+    proptest! {
+        #[test]
+        fn prop_improve_n_kk(ref elements in vec(1_i32..1000, 1..20), n in 2_u8..5) {
+            let partitions = n_kk(elements, n).partitions;
+            let improved = improve(elements, partitions.clone(), None);
+            assert_eq!(improved.len(), n as usize);
+            assert!(improved[0].sum <= partitions[0].sum);
+            let mut union = Subset::empty();
+            for subset in &improved {
+                assert_eq!(union.mask & subset.mask, 0);
+                assert_eq!(*subset, Subset::new(subset.mask, elements));
+                union = Subset::union(&union, subset);
+            }
+            assert_eq!(union, Subset::all(elements));
+       }
+    }
+    #[test]
+    fn unit_improve() {
+        let elements = [5, 4, 3, 3, 3];
+        let partitions = vec![
+            Subset::new(0b00111, &elements),
+            Subset::new(0b11000, &elements),
+        ];
+        let improved = improve(&elements, partitions, None);
+        assert_eq!(improved[0].sum, 9);
+    }
+    #[test]
+    fn unit_improve_no_time() {
+        let elements = [5, 4, 3, 3, 3];
+        let partitions = vec![
+            Subset::new(0b00111, &elements),
+            Subset::new(0b11000, &elements),
+        ];
+        let improved = improve(&elements, partitions.clone(), Some(Duration::from_secs(0)));
+        assert_eq!(improved, partitions);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::arith::Arith;
+use crate::subset::Subset;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::io;
+use std::io::{BufRead, Write};
+
+// Both formats use the same model: a binary x_i_j for every element i and bin j, one
+// assignment row per element forcing it into exactly one bin, and one load row per bin
+// bounding that bin's sum by the continuous makespan variable, which is minimized.
+
+fn variable(i: usize, j: u8) -> String {
+    format!("x_{}_{}", i, j)
+}
+
+fn parse_variable(name: &str) -> Option<(usize, u8)> {
+    if !name.starts_with("x_") {
+        return None;
+    }
+    let mut parts = name["x_".len()..].splitn(2, '_');
+    let i = parts.next()?.parse().ok()?;
+    let j = parts.next()?.parse().ok()?;
+    Some((i, j))
+}
+
+fn signed_term<T: Arith>(coefficient: T, name: &str) -> String {
+    if coefficient < T::from(0) {
+        format!("- {} {}", T::from(0) - coefficient, name)
+    } else {
+        format!("+ {} {}", coefficient, name)
+    }
+}
+
+pub fn write_lp<T: Arith, W: Write>(out: &mut W, elements: &[T], n: u8) -> io::Result<()> {
+    writeln!(
+        out,
+        "\\ Partition {} elements into {} bins",
+        elements.len(),
+        n
+    )?;
+    writeln!(out, "Minimize")?;
+    writeln!(out, " obj: makespan")?;
+    writeln!(out, "Subject To")?;
+    for i in 0..elements.len() {
+        let terms: Vec<String> = (0..n).map(|j| variable(i, j)).collect();
+        writeln!(out, " assign_{}: {} = 1", i, terms.join(" + "))?;
+    }
+    for j in 0..n {
+        let terms: Vec<String> = elements
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| signed_term(x, &variable(i, j)))
+            .collect();
+        writeln!(out, " load_{}: {} - makespan <= 0", j, terms.join(" "))?;
+    }
+    writeln!(out, "Bounds")?;
+    writeln!(out, " makespan >= 0")?;
+    writeln!(out, "Binaries")?;
+    for i in 0..elements.len() {
+        for j in 0..n {
+            writeln!(out, " {}", variable(i, j))?;
+        }
+    }
+    writeln!(out, "End")
+}
+
+fn mps_entry<W: Write, V: Display>(
+    out: &mut W,
+    column: &str,
+    row: &str,
+    value: V,
+) -> io::Result<()> {
+    writeln!(out, "    {:<12} {:<12} {}", column, row, value)
+}
+
+pub fn write_mps<T: Arith, W: Write>(out: &mut W, elements: &[T], n: u8) -> io::Result<()> {
+    writeln!(out, "NAME          PARTITION")?;
+    writeln!(out, "ROWS")?;
+    writeln!(out, " N  obj")?;
+    for i in 0..elements.len() {
+        writeln!(out, " E  assign_{}", i)?;
+    }
+    for j in 0..n {
+        writeln!(out, " L  load_{}", j)?;
+    }
+    writeln!(out, "COLUMNS")?;
+    writeln!(out, "    MARKER       'MARKER'     'INTORG'")?;
+    for (i, &x) in elements.iter().enumerate() {
+        for j in 0..n {
+            let name = variable(i, j);
+            mps_entry(out, &name, &format!("assign_{}", i), 1)?;
+            mps_entry(out, &name, &format!("load_{}", j), x)?;
+        }
+    }
+    writeln!(out, "    MARKER       'MARKER'     'INTEND'")?;
+    mps_entry(out, "makespan", "obj", 1)?;
+    for j in 0..n {
+        mps_entry(out, "makespan", &format!("load_{}", j), -1)?;
+    }
+    writeln!(out, "RHS")?;
+    for i in 0..elements.len() {
+        mps_entry(out, "rhs", &format!("assign_{}", i), 1)?;
+    }
+    writeln!(out, "BOUNDS")?;
+    for i in 0..elements.len() {
+        for j in 0..n {
+            writeln!(out, " BV bnd          {}", variable(i, j))?;
+        }
+    }
+    writeln!(out, "ENDATA")
+}
+
+#[derive(Debug)]
+pub enum SolutionError {
+    Io(io::Error),
+    BadValue { line: usize },
+    UnknownElement(usize),
+    UnknownBin(u8),
+    Unassigned(usize),
+    AssignedTwice(usize),
+}
+
+impl fmt::Display for SolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolutionError::Io(err) => write!(f, "couldn't read the solution: {}", err),
+            SolutionError::BadValue { line } => write!(f, "line {} has no numeric value", line),
+            SolutionError::UnknownElement(i) => write!(f, "element {} doesn't exist", i),
+            SolutionError::UnknownBin(j) => write!(f, "bin {} doesn't exist", j),
+            SolutionError::Unassigned(i) => write!(f, "element {} is in no bin", i),
+            SolutionError::AssignedTwice(i) => write!(f, "element {} is in more than one bin", i),
+        }
+    }
+}
+
+impl Error for SolutionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SolutionError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SolutionError {
+    fn from(err: io::Error) -> Self {
+        SolutionError::Io(err)
+    }
+}
+
+fn attribute<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let rest = &line[line.find(key)? + key.len()..];
+    Some(&rest[..rest.find('"')?])
+}
+
+// Pulls a (name, value) pair out of one line of a solution file. Handles plain
+// "name value" listings (GLPK, Gurobi .sol), CBC's "index name value cost" rows, glpsol's
+// printed "index name * activity lower upper" rows, where the * marks an integer column, and
+// the `<variable name="..." value="..."/>` entries of CPLEX's XML solution format.
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    if line.contains("name=\"") {
+        return Some((attribute(line, "name=\"")?, attribute(line, "value=\"")?));
+    }
+    let mut tokens = line.split_whitespace();
+    let name = tokens
+        .by_ref()
+        .find(|token| parse_variable(token).is_some())?;
+    let value = match tokens.next()? {
+        "*" => tokens.next()?,
+        value => value,
+    };
+    Some((name, value))
+}
+
+pub fn read_solution<T: Arith, R: BufRead>(
+    input: R,
+    elements: &[T],
+    n: u8,
+) -> Result<Vec<Subset<T, u64>>, SolutionError> {
+    let mut bins: Vec<Option<u8>> = vec![None; elements.len()];
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line?;
+        let (name, value) = match parse_line(&line) {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let (i, j) = match parse_variable(name) {
+            Some(ij) => ij,
+            None => continue,
+        };
+        let value: f64 = value.parse().map_err(|_| SolutionError::BadValue {
+            line: line_number + 1,
+        })?;
+        if value < 0.5 {
+            continue;
+        }
+        if i >= elements.len() {
+            return Err(SolutionError::UnknownElement(i));
+        }
+        if j >= n {
+            return Err(SolutionError::UnknownBin(j));
+        }
+        if bins[i].is_some() {
+            return Err(SolutionError::AssignedTwice(i));
+        }
+        bins[i] = Some(j);
+    }
+    let mut partitions = vec![Subset::empty(); n as usize];
+    for (i, bin) in bins.into_iter().enumerate() {
+        let bin = bin.ok_or(SolutionError::Unassigned(i))?;
+        let subset = &mut partitions[bin as usize];
+        *subset = Subset::union(subset, &Subset::from_index(i, elements));
+    }
+    Ok(partitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::milp::{read_solution, write_lp, write_mps, SolutionError};
+    use crate::subset::Subset;
+
+    #[test]
+    fn unit_write_lp() {
+        let elements = [3, 5, 4];
+        let mut out = Vec::new();
+        write_lp(&mut out, &elements, 2).unwrap();
+        let lp = String::from_utf8(out).unwrap();
+        assert!(lp.contains(" assign_1: x_1_0 + x_1_1 = 1\n"));
+        assert!(lp.contains(" load_1: + 3 x_0_1 + 5 x_1_1 + 4 x_2_1 - makespan <= 0\n"));
+        assert!(lp.ends_with("End\n"));
+    }
+    #[test]
+    fn unit_write_mps() {
+        let elements = [3, 5, 4];
+        let mut out = Vec::new();
+        write_mps(&mut out, &elements, 2).unwrap();
+        let mps = String::from_utf8(out).unwrap();
+        assert!(mps.contains(" E  assign_2\n"));
+        assert!(mps.contains(" L  load_1\n"));
+        assert!(mps.contains(" BV bnd          x_2_1\n"));
+        assert!(mps.ends_with("ENDATA\n"));
+    }
+    #[test]
+    fn unit_read_plain_solution() {
+        let elements = [3, 5, 4, 4];
+        let solution = "# Objective value = 8\nmakespan 8\nx_0_0 0\nx_0_1 1\nx_1_0 0\nx_1_1 1\n\
+                        x_2_0 1\nx_2_1 0\nx_3_0 1\nx_3_1 0\n";
+        let partitions = read_solution(solution.as_bytes(), &elements, 2).unwrap();
+        let expected = vec![
+            Subset::new(0b1100, &elements),
+            Subset::new(0b0011, &elements),
+        ];
+        assert_eq!(partitions, expected);
+    }
+    #[test]
+    fn unit_read_cbc_solution() {
+        let elements = [3, 5, 4, 4];
+        let solution = "Optimal - objective value 8.00000000\n\
+                        \x20     0 x_0_1                  1                       0\n\
+                        \x20     3 x_1_1                  1                       0\n\
+                        \x20     4 x_2_0                  1                       0\n\
+                        \x20     6 x_3_0                  1                       0\n\
+                        \x20     8 makespan               8                       1\n";
+        let partitions = read_solution(solution.as_bytes(), &elements, 2).unwrap();
+        let expected = vec![
+            Subset::new(0b1100, &elements),
+            Subset::new(0b0011, &elements),
+        ];
+        assert_eq!(partitions, expected);
+    }
+    #[test]
+    fn unit_read_glpsol_solution() {
+        let elements = [3, 5, 4];
+        let solution = "   No. Column name       Activity     Lower bound   Upper bound\n\
+                        ------ ------------    ------------- ------------- -------------\n\
+                        \x20    1 x_0_0        *              1             0             1\n\
+                        \x20    2 x_0_1        *              0             0             1\n\
+                        \x20    3 x_1_0        *              0             0             1\n\
+                        \x20    4 x_1_1        *              1             0             1\n\
+                        \x20    5 x_2_0        *              1             0             1\n\
+                        \x20    6 x_2_1        *              0             0             1\n\
+                        \x20    7 makespan                    7             0\n";
+        let partitions = read_solution(solution.as_bytes(), &elements, 2).unwrap();
+        let expected = vec![Subset::new(0b101, &elements), Subset::new(0b010, &elements)];
+        assert_eq!(partitions, expected);
+    }
+    #[test]
+    fn unit_read_cplex_solution() {
+        let elements = [3, 5, 4];
+        let solution = "<?xml version = \"1.0\" standalone=\"yes\"?>\n<variables>\n\
+                        \x20 <variable name=\"x_0_0\" index=\"0\" value=\"1\"/>\n\
+                        \x20 <variable name=\"x_1_1\" index=\"3\" value=\"1\"/>\n\
+                        \x20 <variable name=\"x_2_0\" index=\"4\" value=\"1\"/>\n\
+                        \x20 <variable name=\"x_2_1\" index=\"5\" value=\"0\"/>\n\
+                        \x20 <variable name=\"makespan\" index=\"6\" value=\"7\"/>\n\
+                        </variables>\n";
+        let partitions = read_solution(solution.as_bytes(), &elements, 2).unwrap();
+        let expected = vec![Subset::new(0b101, &elements), Subset::new(0b010, &elements)];
+        assert_eq!(partitions, expected);
+    }
+    #[test]
+    fn unit_read_incomplete_solution() {
+        let elements = [3, 5, 4];
+        let solution = "x_0_0 1\nx_1_1 1\n";
+        match read_solution(solution.as_bytes(), &elements, 2) {
+            Err(SolutionError::Unassigned(2)) => {}
+            other => panic!("expected element 2 to be unassigned, got {:?}", other),
+        }
+    }
+    #[test]
+    fn unit_solution_error_display() {
+        let solution = "x_0_0 yes\n";
+        let error = read_solution(solution.as_bytes(), &[3], 1).unwrap_err();
+        assert_eq!(error.to_string(), "line 1 has no numeric value");
+        assert_eq!(
+            SolutionError::AssignedTwice(4).to_string(),
+            "element 4 is in more than one bin"
+        );
+    }
+    #[test]
+    fn unit_read_double_assignment() {
+        let elements = [3, 5];
+        let solution = "x_0_0 1\nx_0_1 1\nx_1_1 1\n";
+        match read_solution(solution.as_bytes(), &elements, 2) {
+            Err(SolutionError::AssignedTwice(0)) => {}
+            other => panic!("expected element 0 to be assigned twice, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::arith::Arith;
+
+// Elements grouped by value. Copies of a value are interchangeable, so a search only needs to
+// decide how many copies go in each bin, not which ones. Searches that pick subsets enforce this
+// by only taking the lowest-indexed copies still available; searches that place elements one at
+// a time by giving each copy a bin no lower than the previous copy's.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Group<T> {
+    value: T,
+    // The indices of the copies, in ascending order.
+    indices: Vec<usize>,
+}
+
+impl<T> Group<T> {
+    fn multiplicity(&self) -> usize {
+        self.indices.len()
+    }
+    fn mask(&self) -> u64 {
+        self.indices.iter().fold(0, |mask, &i| mask | 1 << i)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Multiset<T> {
+    groups: Vec<Group<T>>,
+    // The masks of the groups with more than one copy, the only ones that constrain anything.
+    duplicates: Vec<u64>,
+}
+
+impl<T: Arith> Multiset<T> {
+    // Groups are in order of their first copy.
+    pub fn new(elements: &[T]) -> Self {
+        let mut groups: Vec<Group<T>> = Vec::new();
+        for (i, &x) in elements.iter().enumerate() {
+            match groups.iter_mut().find(|group| group.value == x) {
+                Some(group) => group.indices.push(i),
+                None => groups.push(Group {
+                    value: x,
+                    indices: vec![i],
+                }),
+            }
+        }
+        let duplicates = groups
+            .iter()
+            .filter(|group| group.multiplicity() > 1)
+            .map(Group::mask)
+            .collect();
+        Self { groups, duplicates }
+    }
+
+    // The searches only need previous_copies and is_canonical; the tests check those against
+    // the groups and the counts below.
+    #[cfg(test)]
+    fn groups(&self) -> &[Group<T>] {
+        &self.groups
+    }
+
+    #[cfg(test)]
+    fn has_duplicates(&self) -> bool {
+        !self.duplicates.is_empty()
+    }
+
+    // For each element, the index of the copy of its value just before it, if there is one.
+    pub fn previous_copies(&self) -> Vec<Option<usize>> {
+        let len = self
+            .groups
+            .iter()
+            .flat_map(|group| group.indices.iter())
+            .max()
+            .map_or(0, |&i| i + 1);
+        let mut previous = vec![None; len];
+        for group in &self.groups {
+            for pair in group.indices.windows(2) {
+                previous[pair[1]] = Some(pair[0]);
+            }
+        }
+        previous
+    }
+
+    // Whether, for every value, the copies `submask` takes out of `mask` are the lowest-indexed
+    // copies there. Exactly one subset in each class of interchangeable subsets passes.
+    pub fn is_canonical(&self, submask: u64, mask: u64) -> bool {
+        self.duplicates.iter().all(|&group| {
+            let taken = submask & group;
+            let left = mask & group & !taken;
+            // Every copy taken has to come before every copy left.
+            left == 0 || taken < (left & left.wrapping_neg())
+        })
+    }
+
+    // How many copies of each group's value the mask holds.
+    #[cfg(test)]
+    fn multiplicities(&self, mask: u64) -> Vec<usize> {
+        self.groups
+            .iter()
+            .map(|group| (mask & group.mask()).count_ones() as usize)
+            .collect()
+    }
+
+    // Turns the number of copies of each group in each bin back into a mask per bin, handing
+    // out copies in index order.
+    #[cfg(test)]
+    fn to_masks(&self, counts: &[Vec<usize>]) -> Vec<u64> {
+        let mut masks = vec![0; counts.len()];
+        for (g, group) in self.groups.iter().enumerate() {
+            let mut copies = group.indices.iter();
+            for (mask, bin_counts) in masks.iter_mut().zip(counts) {
+                for &i in copies.by_ref().take(bin_counts[g]) {
+                    *mask |= 1 << i;
+                }
+            }
+            assert!(copies.next().is_none(), "not every copy was placed");
+        }
+        masks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::multiset::Multiset;
+    use crate::subset::submasks;
+    use proptest::collection::vec;
+    use proptest::proptest;
+    use std::collections::HashSet;
+
+    proptest! {
+        #[test]
+        fn prop_canonical_one_per_class(ref elements in vec(1_i32..4, 1..10), mask in 0_u64..1024) {
+            let multiset = Multiset::new(elements);
+            let mask = mask & ((1 << elements.len()) - 1);
+            let mut classes = HashSet::new();
+            for submask in submasks(mask).filter(|&submask| multiset.is_canonical(submask, mask)) {
+                assert!(classes.insert(multiset.multiplicities(submask)));
+                let all = (1 << elements.len()) - 1;
+                let counts = vec![multiset.multiplicities(submask), multiset.multiplicities(all ^ submask)];
+                let masks = multiset.to_masks(&counts);
+                assert_eq!(multiset.multiplicities(masks[0]), counts[0]);
+                assert_eq!((masks[0] & masks[1], masks[0] | masks[1]), (0, all));
+            }
+            let all: HashSet<Vec<usize>> = submasks(mask).map(|submask| multiset.multiplicities(submask)).collect();
+            assert_eq!(classes, all);
+        }
+    }
+    #[test]
+    fn unit_multiset() {
+        let multiset = Multiset::new(&[5, 3, 5, 5, 3, 1]);
+        let groups: Vec<(i32, usize)> = multiset
+            .groups()
+            .iter()
+            .map(|group| (group.value, group.multiplicity()))
+            .collect();
+        assert_eq!(groups, vec![(5, 3), (3, 2), (1, 1)]);
+        assert!(multiset.has_duplicates());
+        assert_eq!(
+            multiset.previous_copies(),
+            vec![None, None, Some(0), Some(2), Some(1), None]
+        );
+        assert!(multiset.is_canonical(0b000101, 0b111111));
+        assert!(!multiset.is_canonical(0b001100, 0b111111));
+        assert!(multiset.is_canonical(0b001100, 0b111100));
+        assert_eq!(
+            multiset.to_masks(&[vec![1, 1, 1], vec![2, 1, 0]]),
+            vec![0b100011, 0b011100]
+        );
+    }
+}
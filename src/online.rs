@@ -0,0 +1,310 @@
+use crate::arith::Arith;
+use crate::large_kk;
+use std::collections::BTreeSet;
+use std::ops::Bound;
+
+// Partitioning items as they arrive, each placed before the next is seen. Works on assignment
+// vectors rather than Subset masks, so the stream can be any length.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Policy {
+    // Graham's list scheduling: always the lightest bin. Never worse than 2 - 1/n times optimal.
+    Greedy,
+    // The heaviest bin the item fits in without going over `slack` times the current lower
+    // bound, falling back to the lightest. Filling bins up early keeps the lighter ones free for
+    // large items still to come.
+    Lookahead { slack: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rebalance {
+    // Rebalance after every this many items.
+    pub every: usize,
+    // Moves allowed per rebalance.
+    pub moves: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report<T> {
+    pub online: T,
+    pub offline: T,
+    // online / offline. The offline result is itself a heuristic, so this can dip below 1.
+    pub ratio: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Online<T> {
+    policy: Policy,
+    rebalance: Option<Rebalance>,
+    elements: Vec<T>,
+    assignment: Vec<usize>,
+    sums: Vec<T>,
+    // The (element, index) pairs in each bin, in order, so rebalancing can look up the element
+    // closest to a target without scanning the stream.
+    #[cfg_attr(
+        feature = "serde",
+        serde(bound(deserialize = "T: serde::Deserialize<'de> + Ord"))
+    )]
+    bins: Vec<BTreeSet<(T, usize)>>,
+    // Kept up to date on each push, for Lookahead's lower bound.
+    total: T,
+    largest: Option<T>,
+    moved: usize,
+}
+
+fn to_f64<T: Arith>(x: T) -> f64 {
+    x.to_f64().expect("element doesn't fit in an f64")
+}
+
+impl<T: Arith> Online<T> {
+    pub fn new(n: u8, policy: Policy) -> Self {
+        assert!(n > 0, "can't partition into 0 bins");
+        Self {
+            policy,
+            rebalance: None,
+            elements: Vec::new(),
+            assignment: Vec::new(),
+            sums: vec![T::from(0); n as usize],
+            bins: vec![BTreeSet::new(); n as usize],
+            total: T::from(0),
+            largest: None,
+            moved: 0,
+        }
+    }
+    pub fn rebalance(self, rebalance: Rebalance) -> Self {
+        Self {
+            rebalance: Some(rebalance),
+            ..self
+        }
+    }
+
+    fn lightest(&self) -> usize {
+        (0..self.sums.len())
+            .min_by_key(|&bin| self.sums[bin])
+            .expect("sums is empty")
+    }
+
+    fn choose(&self, x: T) -> usize {
+        match self.policy {
+            Policy::Greedy => self.lightest(),
+            Policy::Lookahead { slack } => {
+                let n = self.sums.len() as f64;
+                let total = to_f64(self.total + x);
+                let largest = self.largest.map_or(x, |largest| largest.max(x));
+                let limit = slack * (total / n).max(to_f64(largest));
+                (0..self.sums.len())
+                    .filter(|&bin| to_f64(self.sums[bin] + x) <= limit)
+                    .max_by_key(|&bin| self.sums[bin])
+                    .unwrap_or_else(|| self.lightest())
+            }
+        }
+    }
+
+    // Places the item and returns the bin it was placed in. A rebalance may then move it along
+    // with earlier items, so `assignment` has where each item is now.
+    pub fn push(&mut self, x: T) -> usize {
+        let bin = self.choose(x);
+        self.bins[bin].insert((x, self.elements.len()));
+        self.elements.push(x);
+        self.assignment.push(bin);
+        self.sums[bin] += x;
+        self.total += x;
+        self.largest = Some(self.largest.map_or(x, |largest| largest.max(x)));
+        if let Some(rebalance) = self.rebalance {
+            if rebalance.every > 0 && self.elements.len().is_multiple_of(rebalance.every) {
+                self.rebalance_now(rebalance.moves);
+            }
+        }
+        bin
+    }
+
+    // Repeatedly moves the element of the heaviest bin that best evens it out with the lightest,
+    // up to `moves` times.
+    pub fn rebalance_now(&mut self, moves: usize) {
+        for _ in 0..moves {
+            let heavy = (0..self.sums.len())
+                .max_by_key(|&bin| self.sums[bin])
+                .expect("sums is empty");
+            let light = self.lightest();
+            let gap = self.sums[heavy] - self.sums[light];
+            // Moving x leaves max(heavy - x, light + x), which is smallest at x = gap / 2, so
+            // the best element is one of the two either side of it.
+            let half = gap / T::from(2);
+            let below = self.bins[heavy].range(..=(half, usize::MAX)).next_back();
+            let above = self.bins[heavy]
+                .range((Bound::Excluded((half, usize::MAX)), Bound::Unbounded))
+                .next();
+            let candidate = below
+                .into_iter()
+                .chain(above)
+                .filter(|&&(x, _)| x < gap)
+                .min_by_key(|&&(x, _)| (self.sums[heavy] - x).max(self.sums[light] + x))
+                .cloned();
+            let (x, i) = match candidate {
+                Some(candidate) => candidate,
+                None => return,
+            };
+            self.bins[heavy].remove(&(x, i));
+            self.bins[light].insert((x, i));
+            self.sums[heavy] -= x;
+            self.sums[light] += x;
+            self.assignment[i] = light;
+            self.moved += 1;
+        }
+    }
+
+    pub fn assignment(&self) -> &[usize] {
+        &self.assignment
+    }
+    pub fn sums(&self) -> &[T] {
+        &self.sums
+    }
+    pub fn score(&self) -> T {
+        *self.sums.iter().max().expect("sums is empty")
+    }
+    // How many placed items rebalancing has moved.
+    pub fn moved(&self) -> usize {
+        self.moved
+    }
+
+    // Compares the current score with multi-way Karmarkar-Karp on everything seen so far.
+    pub fn report(&self) -> Report<T> {
+        let n = self.sums.len() as u8;
+        let mut offline_sums = vec![T::from(0); self.sums.len()];
+        for (&x, &bin) in self.elements.iter().zip(&large_kk::n_kk(&self.elements, n)) {
+            offline_sums[bin] += x;
+        }
+        let online = self.score();
+        let offline = *offline_sums.iter().max().expect("sums is empty");
+        let ratio = if offline == T::from(0) {
+            1.0
+        } else {
+            to_f64(online) / to_f64(offline)
+        };
+        Report {
+            online,
+            offline,
+            ratio,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::lower_bound;
+    use crate::online::{Online, Policy, Rebalance};
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn assert_consistent(online: &Online<i64>, elements: &[i64]) {
+        let mut sums = vec![0; online.sums().len()];
+        for (&x, &bin) in elements.iter().zip(online.assignment()) {
+            sums[bin] += x;
+        }
+        assert_eq!(sums, online.sums());
+    }
+    proptest! {
+        #[test]
+        fn prop_greedy_bound(ref elements in vec(1_i64..1000, 1..100), n in 2_u8..8) {
+            let mut online = Online::new(n, Policy::Greedy);
+            for &x in elements {
+                online.push(x);
+            }
+            assert_consistent(&online, elements);
+            assert!(online.score() <= 2 * lower_bound(elements, n));
+            assert!(online.report().ratio <= 2.0);
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_lookahead_rebalance(ref elements in vec(1_i64..1000, 1..100), n in 2_u8..8) {
+            let rebalance = Rebalance { every: 10, moves: 3 };
+            let mut online = Online::new(n, Policy::Lookahead { slack: 1.5 }).rebalance(rebalance);
+            for &x in elements {
+                let bin = online.push(x);
+                assert!(bin < n as usize);
+            }
+            assert_consistent(&online, elements);
+            assert!(online.moved() <= elements.len() / 10 * 3);
+            assert!(online.score() >= lower_bound(elements, n));
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_rebalance_best_move(ref elements in vec(1_i64..1000, 1..60), n in 2_u8..5) {
+            let mut online = Online::new(n, Policy::Lookahead { slack: 2.0 });
+            for &x in elements {
+                online.push(x);
+            }
+            let sums = online.sums().to_vec();
+            let heavy = (0..sums.len()).max_by_key(|&bin| sums[bin]).unwrap();
+            let light = (0..sums.len()).min_by_key(|&bin| sums[bin]).unwrap();
+            let gap = sums[heavy] - sums[light];
+            let best = (0..elements.len())
+                .filter(|&i| online.assignment()[i] == heavy && elements[i] < gap)
+                .map(|i| (sums[heavy] - elements[i]).max(sums[light] + elements[i]))
+                .min();
+            online.rebalance_now(1);
+            assert_consistent(&online, elements);
+            match best {
+                Some(best) => assert_eq!(online.sums()[heavy].max(online.sums()[light]), best),
+                None => assert_eq!(online.moved(), 0),
+            }
+        }
+    }
+    #[test]
+    fn unit_lookahead_reserves() {
+        // Greedy splits the small items and then has nowhere good for the large one.
+        let elements = [1, 2, 3];
+        let mut greedy = Online::new(2, Policy::Greedy);
+        let mut lookahead = Online::new(2, Policy::Lookahead { slack: 1.5 });
+        for &x in &elements {
+            greedy.push(x);
+            lookahead.push(x);
+        }
+        assert_eq!(greedy.score(), 4);
+        assert_eq!(lookahead.score(), 3);
+        assert_eq!(lookahead.report().ratio, 1.0);
+    }
+    #[test]
+    fn unit_rebalance() {
+        let mut online = Online::new(2, Policy::Lookahead { slack: 4.0 });
+        let bins: Vec<usize> = [3, 3, 2, 2].iter().map(|&x| online.push(x)).collect();
+        assert!(bins.iter().all(|&bin| bin == bins[0]));
+        online.rebalance_now(10);
+        assert_eq!(online.sums(), &[5, 5]);
+        assert_eq!(online.moved(), 2);
+    }
+    #[test]
+    fn unit_push_returns_placement() {
+        let rebalance = Rebalance {
+            every: 4,
+            moves: 10,
+        };
+        let mut online = Online::new(2, Policy::Lookahead { slack: 4.0 }).rebalance(rebalance);
+        let bins: Vec<usize> = [3, 3, 2, 2].iter().map(|&x| online.push(x)).collect();
+        assert_eq!(bins, vec![1, 1, 1, 1]);
+        assert_eq!(online.assignment(), &[1, 0, 1, 0]);
+    }
+    #[test]
+    #[should_panic(expected = "can't partition into 0 bins")]
+    fn unit_no_bins() {
+        Online::<i64>::new(0, Policy::Greedy);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn unit_resume() {
+        let mut online = Online::new(3, Policy::Greedy).rebalance(Rebalance { every: 2, moves: 1 });
+        for &x in &[5, 4, 3, 3] {
+            online.push(x);
+        }
+        let json = serde_json::to_string(&online).unwrap();
+        let mut resumed: Online<i64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(resumed, online);
+        assert_eq!(resumed.push(2), online.push(2));
+        assert_eq!(resumed, online);
+    }
+}
@@ -0,0 +1,28 @@
+// xorshift64*: small, fast, and stable across releases, so seeded runs stay reproducible
+// without tying results to the output of an external crate's generator.
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // The all-zero state is a fixed point, so scramble the seed away from it.
+        let state = (seed ^ 0x9E37_79B9_7F4A_7C15).max(1);
+        Self { state }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    // Uniform in 0..bound. The modulo bias is negligible for the bounds used here.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+    // Uniform in [0, 1).
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
@@ -1,10 +1,12 @@
-use arith::Arith;
-use ckk::{from_subset, n_kk, KKPartition};
+use crate::arith::Arith;
+use crate::ckk::{from_subset, n_kk, KKPartition};
+use crate::stats;
+use crate::subset::Subset;
 use std::cmp;
 use std::collections::BinaryHeap;
-use subset::Subset;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RNPResult<T: Arith> {
     TwoWay(KKPartition<T>),
     EvenSplit(Box<RNPResult<T>>, Box<RNPResult<T>>),
@@ -49,6 +51,7 @@ fn rnp_helper<T: Arith>(
     upper_bound: &mut T,
     best: &mut RNPResult<T>,
 ) {
+    stats::count_node();
     let mut first = heap.pop().expect("heap is empty");
     match heap.pop() {
         Some(snd) => {
@@ -89,12 +92,11 @@ fn rnp_helper<T: Arith>(
 
 #[cfg(test)]
 mod tests {
-    extern crate test;
-    use self::test::Bencher;
-    use benchmark_data;
+    #[cfg(feature = "serde")]
+    use crate::rnp::{rnp, RNPResult};
+    use crate::select::{compare_partitioning_methods, PartitionMethod};
     use proptest::collection::vec;
-    use rnp::rnp;
-    use select::{compare_partitioning_methods, PartitionMethod};
+    use proptest::proptest;
     #[test]
     fn unit_rnp_gcc_small() {
         let elements = [3, 3, 8, 4, 4, 3, 7];
@@ -103,17 +105,22 @@ mod tests {
     proptest! {
         #[test]
         fn prop_rnp_gcc_small(ref elements in vec(1_i32..10, 1..8)) {
-            compare_partitioning_methods(PartitionMethod::RNP, PartitionMethod::GCC, &elements, 4);
+            compare_partitioning_methods(PartitionMethod::RNP, PartitionMethod::GCC, elements, 4);
        }
     }
     proptest! {
         #[test]
         fn prop_rnp_gcc(ref elements in vec(1_i32..100, 1..10)) {
-            compare_partitioning_methods(PartitionMethod::RNP, PartitionMethod::GCC, &elements, 4);
+            compare_partitioning_methods(PartitionMethod::RNP, PartitionMethod::GCC, elements, 4);
        }
     }
-    #[bench]
-    fn bench_rnp(b: &mut Bencher) {
-        b.iter(|| rnp(&benchmark_data::MEDIUM_ELEMENTS));
+    #[cfg(feature = "serde")]
+    #[test]
+    fn unit_rnp_serde() {
+        let elements = [3, 3, 8, 4, 4, 3, 7, 9, 2];
+        let result = rnp(&elements);
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed: RNPResult<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_vec(), result.to_vec());
     }
 }
@@ -0,0 +1,124 @@
+use crate::stats::{self, Cancelled};
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Once;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Running solvers so that a panic, or a timeout, fails only the one call. Solvers stop
+// themselves at the deadline (see stats::set_deadline), and their threads are joined, so a
+// timed-out solve doesn't keep competing for the CPU.
+
+// Whether a panic on this thread will be caught and reported by catching.
+thread_local!(static CATCHING: Cell<bool> = const { Cell::new(false) });
+
+// Leaves caught panics to be reported through their results, instead of also printing them.
+fn install_quiet_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(Cell::get) {
+                default(info);
+            }
+        }));
+    });
+}
+
+#[derive(Debug)]
+pub(crate) enum Failure {
+    Panicked(String),
+    // The thread's deadline passed.
+    Cancelled,
+}
+
+fn failure(payload: Box<dyn Any + Send>) -> Failure {
+    if payload.is::<Cancelled>() {
+        return Failure::Cancelled;
+    }
+    Failure::Panicked(match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "solver panicked".to_string(),
+        },
+    })
+}
+
+// Runs `solve` on this thread, turning a panic into a Failure.
+pub(crate) fn catching<F: FnOnce() -> R, R>(solve: F) -> Result<R, Failure> {
+    install_quiet_hook();
+    let outer = CATCHING.with(|catching| catching.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(solve));
+    CATCHING.with(|catching| catching.set(outer));
+    result.map_err(failure)
+}
+
+// Runs `solve` on a new thread with the deadline set, and sends what catching returns to
+// `sender`.
+pub(crate) fn spawn<F, R>(
+    solve: F,
+    sender: Sender<Result<R, Failure>>,
+    deadline: Option<Instant>,
+) -> JoinHandle<()>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    thread::spawn(move || {
+        stats::set_deadline(deadline);
+        // The receiver is gone if it has already timed out.
+        let _ = sender.send(catching(solve));
+    })
+}
+
+pub fn with_timeout<F, R>(solve: F, timeout: Option<Duration>) -> Result<R, String>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let (sender, receiver) = channel();
+    let worker = spawn(solve, sender, deadline);
+    let received = match deadline {
+        Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    };
+    let _ = worker.join();
+    match received {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(Failure::Panicked(message))) => Err(message),
+        Ok(Err(Failure::Cancelled)) | Err(RecvTimeoutError::Timeout) => Err(format!(
+            "timed out after {:?}",
+            timeout.expect("only a deadline cancels")
+        )),
+        Err(RecvTimeoutError::Disconnected) => Err("solver exited without a result".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run::with_timeout;
+    use crate::stats;
+    use std::time::Duration;
+
+    #[test]
+    fn unit_cancelled() {
+        // Never finishes on its own, so returning at all means the deadline stopped it.
+        let result: Result<(), String> = with_timeout(
+            || loop {
+                stats::count_node();
+            },
+            Some(Duration::from_millis(20)),
+        );
+        assert_eq!(result, Err("timed out after 20ms".to_string()));
+    }
+    #[test]
+    fn unit_panicked() {
+        let result: Result<(), String> = with_timeout(|| panic!("bad instance"), None);
+        assert_eq!(result, Err("bad instance".to_string()));
+        assert_eq!(with_timeout(|| 3, None), Ok(3));
+    }
+}
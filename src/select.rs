@@ -1,14 +1,13 @@
 use structopt::StructOpt;
 
-use arith::Arith;
-use brute;
-use ckk;
-use gcc;
-use rnp;
-use snp;
-use subset::Subset;
+use crate::arith::Arith;
+use crate::solution::Solution;
+use crate::solver::{Problem, Solver};
+#[cfg(test)]
+use crate::subset::Subset;
 
-#[derive(StructOpt, Debug, Clone, Copy)]
+#[derive(StructOpt, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PartitionMethod {
     #[structopt(name = "kk")]
     KK,
@@ -18,35 +17,42 @@ pub enum PartitionMethod {
     RNP,
     #[structopt(name = "snp")]
     SNP,
+    #[structopt(name = "ss")]
+    SS,
     #[structopt(name = "gcc")]
     GCC,
     #[structopt(name = "brute")]
     Brute,
+    #[structopt(name = "greedy")]
+    Greedy,
+    #[structopt(name = "lpt")]
+    LPT,
+    #[structopt(name = "multifit")]
+    Multifit,
+    #[structopt(name = "cga")]
+    CompleteGreedy {
+        #[structopt(long = "node-limit", default_value = "1000000")]
+        node_limit: u64,
+    },
+    #[structopt(name = "anneal")]
+    Anneal {
+        #[structopt(long = "seed", default_value = "0")]
+        seed: u64,
+        #[structopt(long = "iterations", default_value = "1000000")]
+        iterations: u64,
+    },
 }
 
-pub fn partition_using<T: Arith>(
+// Every method's result as a Solution, with its bins in descending order of their sums and
+// the instance's lower bound attached. Panics where Solver::solve would fail.
+pub fn partition_using<T: Arith + Send + Sync + 'static>(
     method: PartitionMethod,
     elements: &[T],
     n: u8,
-) -> Vec<Subset<T, u64>> {
-    match method {
-        PartitionMethod::KK => ckk::n_kk(elements, n).partitions,
-        PartitionMethod::CKK => {
-            if n != 2 {
-                panic!("ckk is only implemented for 2 partitions right now :(");
-            }
-            ckk::ckk(elements).to_vec()
-        }
-        PartitionMethod::SNP => snp::snp(elements, n),
-        PartitionMethod::GCC => gcc::find_best_partitioning(elements, n).0,
-        PartitionMethod::RNP => {
-            if n != 4 {
-                panic!("rnp is only implemented for 4 partitions right now :(");
-            }
-            rnp::rnp(elements).to_vec()
-        }
-        PartitionMethod::Brute => brute::partition(elements, n),
-    }
+) -> Solution<T> {
+    Solver::new(method)
+        .solve(&Problem::new(elements.to_vec(), n))
+        .unwrap_or_else(|err| panic!("{}", err))
 }
 
 #[cfg(test)]
@@ -67,8 +73,8 @@ pub fn compare_partitioning_methods(
     elements: &[i32],
     n: u8,
 ) {
-    let results_1 = partition_using(m1, elements, n);
-    let results_2 = partition_using(m2, elements, n);
+    let results_1 = partition_using(m1, elements, n).to_subsets(elements);
+    let results_2 = partition_using(m2, elements, n).to_subsets(elements);
     let score_1 = results_1.iter().map(|subset| subset.sum).max().unwrap();
     let score_2 = results_2.iter().map(|subset| subset.sum).max().unwrap();
     assert_eq!(
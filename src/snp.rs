@@ -1,10 +1,12 @@
-use arith::Arith;
-use ckk;
-use ckk::n_kk;
-use ess::biased_iterate_subsets_in_range;
+use crate::arith::Arith;
+use crate::ckk;
+use crate::ckk::n_kk;
+use crate::ess::biased_iterate_subsets_in_range;
+use crate::multiset::Multiset;
+use crate::stats;
+use crate::subset::Subset;
 use std::cmp;
 use std::ops::Range;
-use subset::Subset;
 
 pub fn snp<T: Arith>(elements: &[T], n: u8) -> Vec<Subset<T, u64>> {
     let mask = (1 << elements.len()) - 1;
@@ -13,8 +15,10 @@ pub fn snp<T: Arith>(elements: &[T], n: u8) -> Vec<Subset<T, u64>> {
     let ub = best_partitioning[0].sum;
     let total_remaining = best_partitioning.iter().map(|subset| subset.sum).sum();
     let min_score = (total_remaining - 1.into()) / n.into() + 1.into();
-    let mut snp = SNP {
+    let multiset = Multiset::new(elements);
+    let mut snp = Snp {
         elements,
+        multiset: &multiset,
         n,
         mask,
         current_partitioning: &mut current_partitioning,
@@ -34,8 +38,11 @@ fn partition_range<T: Arith>(ub: T, total: T, n: u8) -> Range<T> {
 }
 
 #[derive(Debug)]
-struct SNP<'a, T> {
+struct Snp<'a, T> {
     elements: &'a [T],
+    //Subsets that differ only in which copies of a value they take lead to the same subtrees,
+    //so only the one taking the lowest-indexed copies is expanded.
+    multiset: &'a Multiset<T>,
     n: u8,
     mask: u64,
     current_partitioning: &'a mut Vec<Subset<T, u64>>,
@@ -51,8 +58,9 @@ struct SNP<'a, T> {
     total_remaining: T,
 }
 
-impl<'a, T: Arith> SNP<'a, T> {
+impl<'a, T: Arith> Snp<'a, T> {
     fn snp_helper(&'a mut self) -> Option<T> {
+        stats::count_node();
         let range = partition_range(self.ub, self.total_remaining, self.n);
         if self.n == 1 {
             let last_subset = Subset::new(self.mask, self.elements);
@@ -72,8 +80,7 @@ impl<'a, T: Arith> SNP<'a, T> {
             if score >= self.ub {
                 return None;
             }
-            self.best_partitioning
-                .clone_from(&self.current_partitioning);
+            self.best_partitioning.clone_from(self.current_partitioning);
             self.best_partitioning.push(partitioning.left);
             self.best_partitioning.push(partitioning.right);
             return Some(cmp::max(self.min_score, score));
@@ -81,12 +88,16 @@ impl<'a, T: Arith> SNP<'a, T> {
         let mut subsets_iterator = biased_iterate_subsets_in_range(self.mask, self.elements, range);
         let mut return_value = None;
         while let Some(first_subset) = subsets_iterator.next() {
+            if !self.multiset.is_canonical(first_subset.mask, self.mask) {
+                continue;
+            }
             let mask = self.mask ^ first_subset.mask;
             let total_remaining = self.total_remaining - first_subset.sum;
             let min_score = cmp::max(self.min_score, first_subset.sum);
             self.current_partitioning.push(first_subset);
-            let mut child = SNP {
+            let mut child = Snp {
                 elements: self.elements,
+                multiset: self.multiset,
                 n: self.n - 1,
                 mask,
                 current_partitioning: self.current_partitioning,
@@ -116,36 +127,39 @@ impl<'a, T: Arith> SNP<'a, T> {
 
 #[cfg(test)]
 mod tests {
-    extern crate cpuprofiler;
-    extern crate test;
-    use self::test::Bencher;
-    use benchmark_data;
+    use crate::select::{compare_partitioning_methods, PartitionMethod};
+    use crate::snp::snp;
+    use crate::subset::Subset;
     use proptest::collection::vec;
-    use select::{compare_partitioning_methods, PartitionMethod};
-    use snp::snp;
-    use subset::Subset;
+    use proptest::proptest;
     proptest! {
         #[test]
         fn prop_snp_gcc(ref elements in vec(1_i32..100, 1..13), n in (2_u8..5)) {
-            compare_partitioning_methods(PartitionMethod::GCC, PartitionMethod::SNP, &elements, n);
+            compare_partitioning_methods(PartitionMethod::GCC, PartitionMethod::SNP, elements, n);
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_snp_gcc_duplicates(ref elements in vec(1_i32..5, 1..13), n in (2_u8..5)) {
+            compare_partitioning_methods(PartitionMethod::GCC, PartitionMethod::SNP, elements, n);
        }
     }
     proptest! {
         #[test]
         fn prop_snp_ckk(ref elements in vec(1_i32..100, 1..10)) {
-            compare_partitioning_methods(PartitionMethod::CKK, PartitionMethod::SNP, &elements, 2);
+            compare_partitioning_methods(PartitionMethod::CKK, PartitionMethod::SNP, elements, 2);
        }
     }
     proptest! {
         #[test]
         fn prop_snp_brute_simple(ref elements in vec(1_i32..6, 1..6)) {
-            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::SNP, &elements, 2);
+            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::SNP, elements, 2);
        }
     }
     proptest! {
         #[test]
         fn prop_snp_brute(ref elements in vec(1_i32..1000, 1..10)) {
-            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::SNP, &elements, 4);
+            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::SNP, elements, 4);
        }
     }
     #[test]
@@ -158,8 +172,9 @@ mod tests {
         ];
         assert_eq!(snp_results, expected);
     }
-    #[bench]
-    fn bench_snp(b: &mut Bencher) {
-        b.iter(|| snp(&benchmark_data::MEDIUM_ELEMENTS, 4));
+    #[test]
+    fn unit_snp_restricted_range() {
+        let elements = [85, 58, 15, 1, 40, 98, 82, 64, 36, 34];
+        compare_partitioning_methods(PartitionMethod::GCC, PartitionMethod::SNP, &elements, 3);
     }
 }
@@ -0,0 +1,184 @@
+use crate::arith::Arith;
+use crate::bounds;
+use crate::ckk::{KKPartition, Partitioning};
+use crate::rnp::RNPResult;
+use crate::select::PartitionMethod;
+use crate::subset::{to_assignment, Subset};
+use std::cmp::Reverse;
+
+// One representation for every solver's result. Bins are numbered in descending order of their
+// sums, so bin 0 always holds the score, whatever order the solver produced them in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Solution<T> {
+    // The bin of each element.
+    pub assignment: Vec<usize>,
+    pub sums: Vec<T>,
+    pub score: T,
+    // The method that produced this, if it came from select.
+    pub method: Option<PartitionMethod>,
+    // bounds::lower_bound for the instance, if it came from select.
+    pub lower_bound: Option<T>,
+}
+
+impl<T: Arith> Solution<T> {
+    // Renumbers the bins in descending order of their sums.
+    fn normalized(assignment: &[usize], sums: &[T]) -> Self {
+        let mut order: Vec<usize> = (0..sums.len()).collect();
+        order.sort_by_key(|&bin| Reverse(sums[bin]));
+        let mut renumbered = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            renumbered[old] = new;
+        }
+        let sums: Vec<T> = order.iter().map(|&bin| sums[bin]).collect();
+        Self {
+            assignment: assignment.iter().map(|&bin| renumbered[bin]).collect(),
+            score: sums.first().cloned().unwrap_or_else(|| T::from(0)),
+            sums,
+            method: None,
+            lower_bound: None,
+        }
+    }
+
+    pub fn from_assignment(assignment: &[usize], elements: &[T], n: u8) -> Self {
+        let mut sums = vec![T::from(0); n as usize];
+        for (&x, &bin) in elements.iter().zip(assignment) {
+            sums[bin] += x;
+        }
+        Self::normalized(assignment, &sums)
+    }
+
+    // Every element has to be in some bin, so the highest bit set gives the element count.
+    pub fn from_subsets(partitions: &[Subset<T, u64>]) -> Self {
+        let union = partitions
+            .iter()
+            .fold(0, |union, subset| union | subset.mask);
+        let len = (64 - union.leading_zeros()) as usize;
+        let sums: Vec<T> = partitions.iter().map(|subset| subset.sum).collect();
+        Self::normalized(&to_assignment(partitions, len), &sums)
+    }
+
+    pub fn with_method(self, method: PartitionMethod) -> Self {
+        Self {
+            method: Some(method),
+            ..self
+        }
+    }
+
+    pub fn with_bound(self, elements: &[T]) -> Self {
+        let n = u8::try_from(self.sums.len()).expect("too many bins");
+        Self {
+            lower_bound: Some(bounds::lower_bound(elements, n)),
+            ..self
+        }
+    }
+
+    // The score's relative gap to the lower bound, if there is one. Zero proves the solution
+    // optimal.
+    pub fn gap(&self) -> Option<f64> {
+        self.lower_bound
+            .map(|lower_bound| bounds::relative_gap(self.score, lower_bound))
+    }
+
+    // The indices of the elements in each bin.
+    pub fn bins(&self) -> Vec<Vec<usize>> {
+        let mut bins = vec![Vec::new(); self.sums.len()];
+        for (i, &bin) in self.assignment.iter().enumerate() {
+            bins[bin].push(i);
+        }
+        bins
+    }
+
+    pub fn to_subsets(&self, elements: &[T]) -> Vec<Subset<T, u64>> {
+        let mut partitions = vec![Subset::empty(); self.sums.len()];
+        for (i, &bin) in self.assignment.iter().enumerate() {
+            partitions[bin] = Subset::union(&partitions[bin], &Subset::from_index(i, elements));
+        }
+        partitions
+    }
+}
+
+impl<T: Arith> From<Vec<Subset<T, u64>>> for Solution<T> {
+    fn from(partitions: Vec<Subset<T, u64>>) -> Self {
+        Self::from_subsets(&partitions)
+    }
+}
+
+impl<T: Arith> From<KKPartition<T>> for Solution<T> {
+    fn from(partition: KKPartition<T>) -> Self {
+        Self::from_subsets(&partition.to_vec())
+    }
+}
+
+impl<T: Arith> From<Partitioning<T>> for Solution<T> {
+    fn from(partitioning: Partitioning<T>) -> Self {
+        Self::from_subsets(&partitioning.partitions)
+    }
+}
+
+impl<T: Arith> From<RNPResult<T>> for Solution<T> {
+    fn from(result: RNPResult<T>) -> Self {
+        Self::from_subsets(&result.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ckk::{ckk, n_kk};
+    use crate::large_kk;
+    use crate::rnp::rnp;
+    use crate::select::{partition_using, PartitionMethod};
+    use crate::solution::Solution;
+    use crate::subset::Subset;
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn assert_consistent(solution: &Solution<i32>, elements: &[i32], n: u8) {
+        assert_eq!(solution.sums.len(), n as usize);
+        assert_eq!(solution.assignment.len(), elements.len());
+        assert!(solution.sums.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert_eq!(solution.score, solution.sums[0]);
+        let mut sums = vec![0; n as usize];
+        for (&x, &bin) in elements.iter().zip(&solution.assignment) {
+            sums[bin] += x;
+        }
+        assert_eq!(sums, solution.sums);
+    }
+    proptest! {
+        #[test]
+        fn prop_partition_using(ref elements in vec(1_i32..1000, 1..10), n in 2_u8..5) {
+            for &method in &[PartitionMethod::KK, PartitionMethod::GCC, PartitionMethod::Greedy] {
+                let solution = partition_using(method, elements, n);
+                assert_consistent(&solution, elements, n);
+                assert_eq!(solution.method, Some(method));
+                assert!(solution.lower_bound.unwrap() <= solution.score);
+            }
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_from_assignment(ref elements in vec(1_i32..1000, 1..40), n in 2_u8..6) {
+            let solution = Solution::from_assignment(&large_kk::n_kk(elements, n), elements, n);
+            assert_consistent(&solution, elements, n);
+        }
+    }
+    #[test]
+    fn unit_conversions() {
+        let elements = [3, 3, 8, 4, 4, 3, 7, 9, 2];
+        assert_consistent(&Solution::from(ckk(&elements)), &elements, 2);
+        assert_consistent(&Solution::from(n_kk(&elements, 3)), &elements, 3);
+        assert_consistent(&Solution::from(rnp(&elements)), &elements, 4);
+    }
+    #[test]
+    fn unit_renumbered() {
+        let elements = [1, 5, 2];
+        let partitions = vec![Subset::new(0b001, &elements), Subset::new(0b110, &elements)];
+        let solution = Solution::from(partitions.clone());
+        assert_eq!(solution.assignment, vec![1, 0, 0]);
+        assert_eq!(solution.sums, vec![7, 1]);
+        assert_eq!(solution.bins(), vec![vec![1, 2], vec![0]]);
+        let mut subsets = solution.to_subsets(&elements);
+        subsets.reverse();
+        assert_eq!(subsets, partitions);
+    }
+}
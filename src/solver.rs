@@ -0,0 +1,643 @@
+use crate::anneal;
+use crate::arith::Arith;
+use crate::brute;
+use crate::ckk;
+use crate::gcc::{self, Limits};
+use crate::greedy;
+use crate::rnp;
+use crate::run::{catching, spawn, Failure};
+use crate::select::PartitionMethod;
+use crate::snp;
+use crate::solution::Solution;
+use crate::ss;
+use crate::stats;
+use std::fmt;
+use std::panic;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Builders for callers that want options, and one entry point whatever the method;
+// select::partition_using is this with the defaults.
+//
+//     let problem = Problem::new(elements, 4)
+//         .capacities(vec![100; 4])
+//         .constraints(vec![Constraint::Together(0, 1)]);
+//     let solution = Solver::new(PartitionMethod::GCC).time_limit(limit).solve(&problem)?;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    // Minimize the largest bin sum.
+    MinMax,
+    // Minimize the difference between the largest and smallest bin sums.
+    MinRange,
+}
+
+impl Objective {
+    pub fn cost<T: Arith>(self, solution: &Solution<T>) -> T {
+        match self {
+            Objective::MinMax => solution.score,
+            Objective::MinRange => match solution.sums.last() {
+                Some(&smallest) => solution.score - smallest,
+                None => T::from(0),
+            },
+        }
+    }
+}
+
+// Constraints on pairs of elements, by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    // The elements go in the same bin.
+    Together(usize, usize),
+    // The elements go in different bins.
+    Apart(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem<T> {
+    elements: Vec<T>,
+    n: u8,
+    objective: Objective,
+    capacities: Option<Vec<T>>,
+    constraints: Vec<Constraint>,
+}
+
+// The group of each element under the Together constraints, numbered from 0 in order of
+// first appearance.
+fn groups(len: usize, constraints: &[Constraint]) -> Vec<usize> {
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut parent: Vec<usize> = (0..len).collect();
+    for &constraint in constraints {
+        if let Constraint::Together(a, b) = constraint {
+            let (a, b) = (root(&mut parent, a), root(&mut parent, b));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+    let mut numbers = vec![usize::MAX; len];
+    let mut next = 0;
+    (0..len)
+        .map(|i| {
+            let r = root(&mut parent, i);
+            if numbers[r] == usize::MAX {
+                numbers[r] = next;
+                next += 1;
+            }
+            numbers[r]
+        })
+        .collect()
+}
+
+impl<T: Arith> Problem<T> {
+    pub fn new(elements: Vec<T>, n: u8) -> Self {
+        Self {
+            elements,
+            n,
+            objective: Objective::MinMax,
+            capacities: None,
+            constraints: Vec::new(),
+        }
+    }
+    // Only used to choose between annealing restarts. Every method minimizes the largest sum,
+    // so a single run is returned whatever its range.
+    pub fn objective(self, objective: Objective) -> Self {
+        Self { objective, ..self }
+    }
+    // One capacity per bin. Only the exact searches, GCC and Brute, can keep to these; other
+    // methods fail with Unsupported.
+    pub fn capacities(self, capacities: Vec<T>) -> Self {
+        Self {
+            capacities: Some(capacities),
+            ..self
+        }
+    }
+    // Together constraints work with every method, which sees each group as one element. Apart
+    // constraints, like capacities, need GCC or Brute.
+    pub fn constraints(self, constraints: Vec<Constraint>) -> Self {
+        Self {
+            constraints,
+            ..self
+        }
+    }
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+    pub fn n(&self) -> u8 {
+        self.n
+    }
+
+    fn validate(&self) -> Result<(), SolveError> {
+        if self.n == 0 {
+            return Err(SolveError::Invalid("n must be at least 1".to_string()));
+        }
+        if let Some(ref capacities) = self.capacities {
+            if capacities.len() != self.n as usize {
+                return Err(SolveError::Invalid(format!(
+                    "expected {} capacities",
+                    self.n
+                )));
+            }
+        }
+        let len = self.elements.len();
+        for &constraint in &self.constraints {
+            let (Constraint::Together(a, b) | Constraint::Apart(a, b)) = constraint;
+            if a >= len || b >= len {
+                return Err(SolveError::Invalid(format!(
+                    "{:?} refers past the {} elements",
+                    constraint, len
+                )));
+            }
+        }
+        let groups = groups(len, &self.constraints);
+        for &constraint in &self.constraints {
+            match constraint {
+                Constraint::Apart(a, b) if groups[a] == groups[b] => {
+                    return Err(SolveError::Invalid(format!(
+                        "{:?} contradicts the Together constraints",
+                        constraint
+                    )))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // The capacities and Apart constraints, in terms of the merged elements, if there are any.
+    fn limits(&self, groups: &[usize]) -> Option<Limits<T>> {
+        let apart: Vec<(usize, usize)> = self
+            .constraints
+            .iter()
+            .filter_map(|&constraint| match constraint {
+                Constraint::Apart(a, b) => Some((groups[a], groups[b])),
+                Constraint::Together(..) => None,
+            })
+            .collect();
+        if self.capacities.is_none() && apart.is_empty() {
+            return None;
+        }
+        Some(Limits {
+            capacities: self.capacities.clone(),
+            apart,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SolveError {
+    Invalid(String),
+    TimedOut,
+    // The method panicked, for example because it doesn't support this many bins.
+    Failed(String),
+    // The method can't keep to capacities or Apart constraints.
+    Unsupported(PartitionMethod),
+    // No partitioning keeps to the capacities and constraints.
+    Infeasible,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Invalid(message) => write!(f, "invalid problem: {}", message),
+            SolveError::TimedOut => write!(f, "timed out"),
+            SolveError::Failed(message) => write!(f, "solver failed: {}", message),
+            SolveError::Unsupported(method) => write!(
+                f,
+                "{:?} can't keep to capacities or Apart constraints, only GCC and Brute can",
+                method
+            ),
+            SolveError::Infeasible => {
+                write!(f, "no partitioning meets the capacities and constraints")
+            }
+        }
+    }
+}
+
+type Observer<T> = Arc<dyn Fn(&Solution<T>) + Send + Sync>;
+
+pub struct Solver<T> {
+    method: PartitionMethod,
+    time_limit: Option<Duration>,
+    threads: usize,
+    seed: Option<u64>,
+    observer: Option<Observer<T>>,
+}
+
+impl<T: Arith + Send + Sync + 'static> Solver<T> {
+    pub fn new(method: PartitionMethod) -> Self {
+        Self {
+            method,
+            time_limit: None,
+            threads: 1,
+            seed: None,
+            observer: None,
+        }
+    }
+    // Annealing stops at the limit and returns the best it has found. Searches stop and fail
+    // with TimedOut; methods that don't search always finish, and fail if they finish late.
+    pub fn time_limit(self, time_limit: Duration) -> Self {
+        Self {
+            time_limit: Some(time_limit),
+            ..self
+        }
+    }
+    // Annealing runs this many independent restarts in parallel, with consecutive seeds. The
+    // other methods are deterministic, so they always run once.
+    pub fn threads(self, threads: usize) -> Self {
+        Self { threads, ..self }
+    }
+    // Overrides the seed of randomized methods.
+    pub fn seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+    // Called with each solution that improves on the best so far.
+    pub fn observer<F: Fn(&Solution<T>) + Send + Sync + 'static>(self, observer: F) -> Self {
+        Self {
+            observer: Some(Arc::new(observer)),
+            ..self
+        }
+    }
+
+    // Every method's result as a Solution. The modules' own entry points keep returning their
+    // native types, which Solution converts from.
+    // Returns None if nothing keeps to the limits.
+    fn run(
+        &self,
+        restart: u64,
+        elements: &[T],
+        n: u8,
+        limits: Option<&Limits<T>>,
+    ) -> Option<Solution<T>> {
+        let mut method = self.method;
+        let solution = match method {
+            PartitionMethod::KK => ckk::n_kk(elements, n).into(),
+            PartitionMethod::CKK => {
+                if n != 2 {
+                    panic!("ckk is only implemented for 2 partitions right now :(");
+                }
+                ckk::ckk(elements).into()
+            }
+            PartitionMethod::SNP => snp::snp(elements, n).into(),
+            PartitionMethod::SS => {
+                if n != 2 {
+                    panic!("ss is only implemented for 2 partitions");
+                }
+                ss::ss(elements).to_vec().into()
+            }
+            PartitionMethod::GCC => match limits {
+                Some(limits) => gcc::find_best_partitioning_with_limits(elements, n, limits)?
+                    .0
+                    .into(),
+                None => gcc::find_best_partitioning(elements, n).0.into(),
+            },
+            PartitionMethod::RNP => {
+                if n != 4 {
+                    panic!("rnp is only implemented for 4 partitions right now :(");
+                }
+                rnp::rnp(elements).into()
+            }
+            PartitionMethod::Brute => match limits {
+                Some(limits) => brute::partition_with_limits(elements, n, limits)?.into(),
+                None => brute::partition(elements, n).into(),
+            },
+            PartitionMethod::Greedy => greedy::greedy(elements, n).into(),
+            PartitionMethod::LPT => greedy::lpt(elements, n).into(),
+            PartitionMethod::Multifit => {
+                greedy::multifit(elements, n, greedy::MULTIFIT_ITERATIONS).into()
+            }
+            PartitionMethod::CompleteGreedy { node_limit } => {
+                greedy::complete(elements, n, node_limit).into()
+            }
+            // Works on an assignment, so it isn't limited to the 64 elements of a mask.
+            PartitionMethod::Anneal { seed, iterations } => {
+                let options = anneal::Options {
+                    seed: self.seed.unwrap_or(seed).wrapping_add(restart),
+                    iterations,
+                    time_limit: self.time_limit,
+                };
+                method = PartitionMethod::Anneal {
+                    seed: options.seed,
+                    iterations,
+                };
+                let assignment = anneal::from_kk(elements, n, &options);
+                Solution::from_assignment(&assignment, elements, n)
+            }
+        };
+        Some(solution.with_method(method).with_bound(elements))
+    }
+
+    // The solvers see each group of Together elements as one element. A single run with no
+    // deadline stays on this thread.
+    pub fn solve(self, problem: &Problem<T>) -> Result<Solution<T>, SolveError> {
+        problem.validate()?;
+        let groups = groups(problem.elements.len(), &problem.constraints);
+        let mut merged = vec![T::from(0); groups.iter().max().map_or(0, |&group| group + 1)];
+        for (&x, &group) in problem.elements.iter().zip(&groups) {
+            merged[group] += x;
+        }
+        let limits = problem.limits(&groups);
+        if limits.is_some() && !matches!(self.method, PartitionMethod::GCC | PartitionMethod::Brute)
+        {
+            return Err(SolveError::Unsupported(self.method));
+        }
+        let (restarts, deadline) = match self.method {
+            PartitionMethod::Anneal { .. } => (self.threads.max(1), None),
+            _ => (1, self.time_limit.map(|limit| Instant::now() + limit)),
+        };
+        let n = problem.n;
+        let solver = Arc::new(self);
+        let (sender, receiver) = channel();
+        let mut workers = Vec::new();
+        if restarts == 1 && deadline.is_none() {
+            // A deadline set by the caller, such as with_timeout's, belongs to the caller, so
+            // it's passed on rather than reported as this solve's own timeout.
+            match catching(|| solver.run(0, &merged, n, limits.as_ref())) {
+                Err(Failure::Cancelled) => panic::resume_unwind(Box::new(stats::Cancelled)),
+                result => {
+                    let _ = sender.send(result);
+                }
+            }
+        } else {
+            let instance = Arc::new((merged, limits));
+            for restart in 0..restarts {
+                let solver = Arc::clone(&solver);
+                let instance = Arc::clone(&instance);
+                workers.push(spawn(
+                    move || {
+                        let (ref merged, ref limits) = *instance;
+                        solver.run(restart as u64, merged, n, limits.as_ref())
+                    },
+                    sender.clone(),
+                    deadline,
+                ));
+            }
+        }
+        drop(sender);
+        let mut best: Option<Solution<T>> = None;
+        let mut failed = None;
+        for _ in 0..restarts {
+            let received = match deadline {
+                Some(deadline) => {
+                    receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let solution = match received {
+                Ok(Ok(Some(solution))) => solution,
+                Ok(Ok(None)) => {
+                    failed = Some(SolveError::Infeasible);
+                    break;
+                }
+                Ok(Err(Failure::Panicked(message))) => {
+                    failed = Some(SolveError::Failed(message));
+                    break;
+                }
+                Ok(Err(Failure::Cancelled)) | Err(RecvTimeoutError::Timeout) => {
+                    failed = Some(SolveError::TimedOut);
+                    break;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    failed = Some(SolveError::Failed("solver thread exited".to_string()));
+                    break;
+                }
+            };
+            let solution = Solution {
+                assignment: groups
+                    .iter()
+                    .map(|&group| solution.assignment[group])
+                    .collect(),
+                ..solution
+            };
+            let cost = problem.objective.cost(&solution);
+            if best
+                .as_ref()
+                .is_none_or(|best| cost < problem.objective.cost(best))
+            {
+                if let Some(ref observer) = solver.observer {
+                    observer(&solution);
+                }
+                best = Some(solution);
+            }
+        }
+        // Searches stop at the deadline, so this doesn't wait long after a timeout.
+        for worker in workers {
+            let _ = worker.join();
+        }
+        if let Some(err) = failed {
+            return Err(err);
+        }
+        Ok(best.expect("no restarts ran"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::benchmark_data;
+    use crate::gcc;
+    use crate::large_kk;
+    use crate::run::with_timeout;
+    use crate::select::PartitionMethod;
+    use crate::solution::Solution;
+    use crate::solver::{Constraint, Objective, Problem, SolveError, Solver};
+    use proptest::collection::vec;
+    use proptest::proptest;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    proptest! {
+        #[test]
+        fn prop_solver_matches_select(ref elements in vec(1_i32..1000, 1..10), n in 2_u8..5) {
+            let problem = Problem::new(elements.clone(), n);
+            let solution = Solver::new(PartitionMethod::GCC).solve(&problem).unwrap();
+            let expected = Solution::from(gcc::find_best_partitioning(elements, n).0)
+                .with_method(PartitionMethod::GCC)
+                .with_bound(elements);
+            assert_eq!(solution, expected);
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_together(ref elements in vec(1_i32..1000, 2..10), n in 2_u8..5) {
+            let last = elements.len() - 1;
+            let problem = Problem::new(elements.clone(), n)
+                .constraints(vec![Constraint::Together(0, last), Constraint::Together(last, 1)]);
+            let solution = Solver::new(PartitionMethod::GCC).solve(&problem).unwrap();
+            assert_eq!(solution.assignment[0], solution.assignment[last]);
+            assert_eq!(solution.assignment[1], solution.assignment[last]);
+            let expanded = Solution::from_assignment(&solution.assignment, elements, n);
+            assert_eq!(solution.sums, expanded.sums);
+            let unconstrained = Solver::new(PartitionMethod::GCC).solve(&Problem::new(elements.clone(), n));
+            assert!(solution.score >= unconstrained.unwrap().score);
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_limits_gcc_brute(
+            ref elements in vec(1_i32..50, 2..8),
+            n in 2_u8..4,
+            ref capacities in vec(20_i32..120, 3),
+            a in 0_usize..8,
+            b in 0_usize..8,
+        ) {
+            let (a, b) = (a % elements.len(), b % elements.len());
+            let mut constraints = Vec::new();
+            if a != b {
+                constraints.push(Constraint::Apart(a, b));
+            }
+            let problem = Problem::new(elements.clone(), n)
+                .capacities(capacities[..n as usize].to_vec())
+                .constraints(constraints);
+            let gcc = Solver::new(PartitionMethod::GCC).solve(&problem);
+            let brute = Solver::new(PartitionMethod::Brute).solve(&problem);
+            match (gcc, brute) {
+                (Ok(gcc), Ok(brute)) => {
+                    assert_eq!(gcc.score, brute.score);
+                    if a != b {
+                        assert_ne!(gcc.assignment[a], gcc.assignment[b]);
+                    }
+                    let mut capacities = capacities[..n as usize].to_vec();
+                    capacities.sort_by(|l, r| r.cmp(l));
+                    assert!(gcc.sums.iter().zip(&capacities).all(|(sum, capacity)| sum <= capacity));
+                }
+                (gcc, brute) => assert_eq!(gcc, brute),
+            }
+        }
+    }
+    #[test]
+    fn unit_capacities() {
+        let problem = Problem::new(vec![5, 4, 3, 3, 3], 2).capacities(vec![9, 9]);
+        let solution = Solver::new(PartitionMethod::GCC).solve(&problem).unwrap();
+        assert_eq!(solution.sums, vec![9, 9]);
+        // 5 + 3 fits the smaller bin and 4 + 3 + 3 the larger one.
+        for method in [PartitionMethod::GCC, PartitionMethod::Brute] {
+            let problem = problem.clone().capacities(vec![8, 10]);
+            let solution = Solver::new(method).solve(&problem).unwrap();
+            assert_eq!(solution.sums, vec![10, 8]);
+            let problem = problem.capacities(vec![8, 9]);
+            assert_eq!(
+                Solver::new(method).solve(&problem),
+                Err(SolveError::Infeasible)
+            );
+        }
+        let problem = problem.capacities(vec![8, 10]);
+        assert_eq!(
+            Solver::new(PartitionMethod::KK).solve(&problem),
+            Err(SolveError::Unsupported(PartitionMethod::KK))
+        );
+        let problem = problem.capacities(vec![9]);
+        match Solver::new(PartitionMethod::GCC).solve(&problem) {
+            Err(SolveError::Invalid(_)) => {}
+            other => panic!("expected an invalid problem, got {:?}", other),
+        }
+    }
+    #[test]
+    fn unit_constraints() {
+        let problem = Problem::new(vec![2, 1, 1], 2);
+        let solution = Solver::new(PartitionMethod::GCC).solve(&problem).unwrap();
+        assert_eq!(solution.assignment, vec![0, 1, 1]);
+        // {2, 1} and {1} keeps the copies of 1 apart.
+        let problem = problem.constraints(vec![Constraint::Apart(1, 2)]);
+        for method in [PartitionMethod::GCC, PartitionMethod::Brute] {
+            let solution = Solver::new(method).solve(&problem).unwrap();
+            assert_eq!(solution.sums, vec![3, 1]);
+            assert_ne!(solution.assignment[1], solution.assignment[2]);
+        }
+        assert_eq!(
+            Solver::new(PartitionMethod::Greedy).solve(&problem),
+            Err(SolveError::Unsupported(PartitionMethod::Greedy))
+        );
+        let apart = Problem::new(vec![1, 1, 1], 2).constraints(vec![
+            Constraint::Apart(0, 1),
+            Constraint::Apart(1, 2),
+            Constraint::Apart(0, 2),
+        ]);
+        assert_eq!(
+            Solver::new(PartitionMethod::GCC).solve(&apart),
+            Err(SolveError::Infeasible)
+        );
+        let problem = problem.constraints(vec![Constraint::Together(0, 1)]);
+        let solution = Solver::new(PartitionMethod::KK).solve(&problem).unwrap();
+        assert_eq!(solution.assignment, vec![0, 0, 1]);
+        assert_eq!(solution.sums, vec![3, 1]);
+        for constraints in [
+            vec![Constraint::Together(0, 1), Constraint::Apart(1, 0)],
+            vec![Constraint::Apart(0, 3)],
+        ] {
+            let problem = problem.clone().constraints(constraints);
+            match Solver::new(PartitionMethod::GCC).solve(&problem) {
+                Err(SolveError::Invalid(_)) => {}
+                other => panic!("expected an invalid problem, got {:?}", other),
+            }
+        }
+    }
+    #[test]
+    fn unit_failed() {
+        let problem = Problem::new(vec![5, 4, 3], 3);
+        match Solver::new(PartitionMethod::CKK).solve(&problem) {
+            Err(SolveError::Failed(_)) => {}
+            other => panic!("expected ckk to fail on 3 bins, got {:?}", other),
+        }
+    }
+    #[test]
+    fn unit_timed_out() {
+        let problem = Problem::new(benchmark_data::BIG_ELEMENTS.to_vec(), 4);
+        // Without a node limit this search runs far longer than any test, until the deadline
+        // stops it.
+        let method = PartitionMethod::CompleteGreedy {
+            node_limit: u64::MAX,
+        };
+        let result = Solver::new(method)
+            .time_limit(Duration::from_millis(50))
+            .solve(&problem);
+        assert_eq!(result, Err(SolveError::TimedOut));
+    }
+    #[test]
+    fn unit_caller_deadline() {
+        // The caller's timeout, not this solve's, so it's reported by with_timeout.
+        let method = PartitionMethod::CompleteGreedy {
+            node_limit: u64::MAX,
+        };
+        let result = with_timeout(
+            move || {
+                let problem = Problem::new(benchmark_data::BIG_ELEMENTS.to_vec(), 4);
+                Solver::new(method).solve(&problem)
+            },
+            Some(Duration::from_millis(20)),
+        );
+        assert_eq!(result, Err("timed out after 20ms".to_string()));
+    }
+    #[test]
+    fn unit_anneal_restarts() {
+        let elements = benchmark_data::BIG_ELEMENTS.to_vec();
+        let problem = Problem::new(elements.clone(), 4).objective(Objective::MinRange);
+        let improvements = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&improvements);
+        let method = PartitionMethod::Anneal {
+            seed: 0,
+            iterations: 10_000,
+        };
+        let solution = Solver::new(method)
+            .threads(4)
+            .seed(7)
+            .observer(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .solve(&problem)
+            .unwrap();
+        // Every restart starts from the same KK assignment and keeps its best score.
+        let kk = Solution::from_assignment(&large_kk::n_kk(&elements, 4), &elements, 4);
+        assert!(solution.score <= kk.score);
+        let count = improvements.load(Ordering::SeqCst);
+        assert!((1..=4).contains(&count));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::arith::Arith;
+use crate::ckk::KKPartition;
+use crate::stats;
+use crate::subset::{ordered_subsets, split_mask, Down, OrderedSubsets, Subset, Up};
+
+// Schroeppel-Shamir: walk the subsets of one half in ascending order and the subsets of the
+// other half in descending order, looking for the largest union sum that doesn't exceed the
+// target. Each half is itself generated in sorted order from its quarters by ordered_subsets,
+// so memory is O(2^(n/4)) and time is O(2^(n/2) log n).
+pub fn closest_below<T: Arith>(mask: u64, elements: &[T], target: T) -> Option<Subset<T, u64>> {
+    let (left, right) = split_mask(mask, elements);
+    let mut ascending: OrderedSubsets<_, Up> = ordered_subsets(left, elements);
+    let mut descending: OrderedSubsets<_, Down> = ordered_subsets(right, elements);
+    let mut small = ascending.next()?;
+    let mut large = descending.next()?;
+    let mut best: Option<Subset<T, u64>> = None;
+    loop {
+        stats::count_node();
+        let union = Subset::union(&small, &large);
+        if union.sum <= target {
+            let improved = match best {
+                None => true,
+                Some(ref best) => union.sum > best.sum,
+            };
+            if improved {
+                let perfect = union.sum == target;
+                best = Some(union);
+                if perfect {
+                    return best;
+                }
+            }
+            small = match ascending.next() {
+                None => return best,
+                Some(small) => small,
+            };
+        } else {
+            large = match descending.next() {
+                None => return best,
+                Some(large) => large,
+            };
+        }
+    }
+}
+
+pub fn ss<T: Arith>(elements: &[T]) -> KKPartition<T> {
+    let all = Subset::all(elements);
+    let half = all.sum / T::from(2);
+    let right = closest_below(all.mask, elements, half).expect("the empty subset is always below");
+    let left = Subset {
+        sum: all.sum - right.sum,
+        mask: all.mask ^ right.mask,
+    };
+    KKPartition { left, right }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::select::{compare_partitioning_methods, PartitionMethod};
+    use crate::ss::ss;
+    use crate::stats;
+    use crate::subset::Subset;
+    use proptest::collection::vec;
+    use proptest::proptest;
+    proptest! {
+        #[test]
+        fn prop_ss_ckk(ref elements in vec(1_i32..100, 1..16)) {
+            compare_partitioning_methods(PartitionMethod::CKK, PartitionMethod::SS, elements, 2);
+       }
+    }
+    proptest! {
+        #[test]
+        fn prop_ss_brute(ref elements in vec(1_i32..1000, 1..10)) {
+            compare_partitioning_methods(PartitionMethod::Brute, PartitionMethod::SS, elements, 2);
+       }
+    }
+    #[test]
+    fn unit_ss() {
+        let elements = [24, 17, 24, 25, 25];
+        stats::take_nodes();
+        let partition = ss(&elements);
+        assert!(stats::take_nodes() > 0);
+        assert_eq!(partition.left, Subset::new(0b00111, &elements));
+        assert_eq!(partition.right, Subset::new(0b11000, &elements));
+    }
+}
@@ -0,0 +1,43 @@
+use std::cell::Cell;
+use std::panic;
+use std::time::Instant;
+
+// Search tree nodes visited on this thread. Kept thread local so the recursive solvers don't
+// need an extra parameter, and so concurrent solves don't mix their counts.
+thread_local!(static NODES: Cell<u64> = const { Cell::new(0) });
+
+// When searches on this thread should give up. Thread local for the same reasons.
+thread_local!(static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) });
+
+// What count_node unwinds with once the deadline has passed. run catches it and reports a
+// timeout.
+#[derive(Debug)]
+pub struct Cancelled;
+
+pub fn count_node() {
+    let nodes = NODES.with(|nodes| {
+        nodes.set(nodes.get() + 1);
+        nodes.get()
+    });
+    // Reading the clock on every node would cost more than the nodes themselves.
+    if nodes.is_multiple_of(1024) && past_deadline() {
+        panic::resume_unwind(Box::new(Cancelled));
+    }
+}
+
+// Returns the number of nodes counted on this thread since the last call, and resets it.
+pub fn take_nodes() -> u64 {
+    NODES.with(|nodes| nodes.replace(0))
+}
+
+// Searches that count nodes stop at the deadline, and annealing returns the best it has found.
+// Methods that don't search run to completion.
+pub fn set_deadline(deadline: Option<Instant>) {
+    DEADLINE.with(|cell| cell.set(deadline));
+}
+
+pub fn past_deadline() -> bool {
+    DEADLINE
+        .with(Cell::get)
+        .is_some_and(|deadline| Instant::now() >= deadline)
+}
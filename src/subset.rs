@@ -7,6 +7,7 @@ use std::marker::PhantomData;
 use std::mem::swap;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Subset<T, M> {
     pub sum: T,
     pub mask: M,
@@ -34,8 +35,14 @@ impl<T: Arith> Subset<T, u64> {
             mask: 0,
         }
     }
+    // Masks hold at most 64 elements. Shifting right, rather than 1 << len, keeps 64 itself
+    // from overflowing.
     pub fn all(elements: &[T]) -> Self {
-        let mask = (1 << elements.len()) - 1;
+        assert!(elements.len() <= 64, "masks hold at most 64 elements");
+        let mask = match elements.len() {
+            0 => 0,
+            len => u64::MAX >> (64 - len),
+        };
         let sum = elements.iter().cloned().sum();
         Self { sum, mask }
     }
@@ -69,7 +76,32 @@ impl<'a, T: Arith> Subset<T, u64> {
     }
 }
 
-pub fn all_subsets<T: Arith>(elements: &[T]) -> Option<(Vec<Subset<T, u64>>)> {
+// Bin index of each element, for code that works on assignment vectors instead of masks.
+pub fn to_assignment<T: Arith>(partitions: &[Subset<T, u64>], len: usize) -> Vec<usize> {
+    let mut assignment = vec![0; len];
+    for (bin, subset) in partitions.iter().enumerate() {
+        for (i, slot) in assignment.iter_mut().enumerate() {
+            if subset.mask & (1 << i) > 0 {
+                *slot = bin;
+            }
+        }
+    }
+    assignment
+}
+
+pub fn from_assignment<T: Arith>(
+    assignment: &[usize],
+    elements: &[T],
+    n: u8,
+) -> Vec<Subset<T, u64>> {
+    let mut partitions = vec![Subset::empty(); n as usize];
+    for (i, &bin) in assignment.iter().enumerate() {
+        partitions[bin] = Subset::union(&partitions[bin], &Subset::from_index(i, elements));
+    }
+    partitions
+}
+
+pub fn all_subsets<T: Arith>(elements: &[T]) -> Option<Vec<Subset<T, u64>>> {
     if elements.len() > 63 {
         //TODO: 64 is doable but requires care on the bitshift
         return None;
@@ -106,7 +138,7 @@ pub struct Submasks {
     submask: u64,
     start: bool,
 }
-impl Iterator for Submasks where {
+impl Iterator for Submasks {
     type Item = u64;
     fn next(&mut self) -> Option<u64> {
         if self.start {
@@ -181,8 +213,7 @@ impl<T: Arith, D> Eq for HeapPair<T, D> {}
 //Orderings are reversed since we want a min-heap
 impl<T: Arith, D: OrderingDirection> PartialOrd for HeapPair<T, D> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        <D as OrderingDirection>::partial_cmp(&self.union.sum, &other.union.sum)
-            .map(|o| o.reverse())
+        Some(self.cmp(other))
     }
 }
 impl<T: Arith, D: OrderingDirection> Ord for HeapPair<T, D> {
@@ -229,7 +260,7 @@ impl<T: Arith, D: OrderingDirection + Debug> Iterator for OrderedSubsets<T, D> {
         match self.vec.get(pair.index) {
             None => Some(PeekMut::pop(pair).union),
             Some(unfixed) => {
-                let mut next_union = Subset::union(&unfixed, &pair.fixed);
+                let mut next_union = Subset::union(unfixed, &pair.fixed);
                 swap(&mut pair.union, &mut next_union);
                 Some(next_union)
             }
@@ -239,11 +270,9 @@ impl<T: Arith, D: OrderingDirection + Debug> Iterator for OrderedSubsets<T, D> {
 
 #[cfg(test)]
 mod tests {
-    extern crate test;
-    use self::test::Bencher;
-    use benchmark_data;
+    use crate::subset::{all_subsets, ordered_subsets, Down, OrderedSubsets, Subset, Up};
     use proptest::collection::vec;
-    use subset::{all_subsets, ordered_subsets, Down, OrderedSubsets, Up};
+    use proptest::proptest;
     proptest! {
         #[test]
         fn prop_ordered_subsets(ref elements in vec(1_i32..100, 1..10)) {
@@ -276,10 +305,23 @@ mod tests {
             );
        }
     }
-    #[bench]
-    fn bench_ordered_subsets(b: &mut Bencher) {
-        let elements = &benchmark_data::SMALL_ELEMENTS;
-        let mask = (1 << elements.len()) - 1;
-        b.iter(|| ordered_subsets::<i32, Up>(mask, elements).fold(0, |acc, x| acc ^ x.sum));
+    #[test]
+    fn unit_all() {
+        assert_eq!(Subset::<i32, u64>::all(&[]).mask, 0);
+        assert_eq!(
+            Subset::all(&[4, 5, 6]),
+            Subset {
+                sum: 15,
+                mask: 0b111
+            }
+        );
+        let elements = [1; 64];
+        assert_eq!(
+            Subset::all(&elements),
+            Subset {
+                sum: 64,
+                mask: u64::MAX
+            }
+        );
     }
 }
@@ -0,0 +1,229 @@
+use crate::arith::Arith;
+use crate::ess::{count_subsets_in_range, iterate_subsets_in_range};
+use crate::ss::closest_below;
+use crate::subset::Subset;
+use std::ops::Range;
+
+// Finding a subset of non-negative elements whose sum hits or comes close to a target. Small
+// totals are solved by dynamic programming over the sums, anything larger by meet in the middle.
+
+// Totals up to this use the dynamic program, which takes time and memory linear in the total.
+const DP_LIMIT: usize = 1 << 16;
+
+fn dp_total<T: Arith>(elements: &[T]) -> Option<usize> {
+    let total = elements.iter().cloned().sum::<T>().to_usize()?;
+    if total <= DP_LIMIT {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+// The subsets are returned as masks, which hold at most 64 elements.
+fn check_len<T>(elements: &[T]) {
+    assert!(elements.len() <= 64, "masks hold at most 64 elements");
+}
+
+fn reached(last: &[Option<usize>], sum: usize) -> bool {
+    sum == 0 || last[sum].is_some()
+}
+
+// For each sum up to the total, the last element added to first reach it, if it can be reached.
+// Sums are only ever reached from sums without that element, so following them back gives a
+// subset. The empty subset reaches 0.
+fn reachable<T: Arith>(elements: &[T], total: usize) -> Vec<Option<usize>> {
+    let mut last = vec![None; total + 1];
+    for (i, x) in elements.iter().enumerate() {
+        let x = x.to_usize().expect("elements must be non-negative");
+        if x == 0 {
+            continue;
+        }
+        for sum in (x..=total).rev() {
+            if !reached(&last, sum) && reached(&last, sum - x) {
+                last[sum] = Some(i);
+            }
+        }
+    }
+    last
+}
+
+fn rebuild<T: Arith>(elements: &[T], last: &[Option<usize>], mut sum: usize) -> Subset<T, u64> {
+    let mut mask = 0;
+    while sum > 0 {
+        let i = last[sum].expect("sum isn't reachable");
+        mask |= 1 << i;
+        sum -= elements[i]
+            .to_usize()
+            .expect("elements must be non-negative");
+    }
+    Subset::new(mask, elements)
+}
+
+fn dp_below<T: Arith>(elements: &[T], total: usize, target: T) -> Option<Subset<T, u64>> {
+    let target = target.to_usize()?.min(total);
+    let last = reachable(elements, total);
+    let sum = (0..=target).rev().find(|&sum| reached(&last, sum))?;
+    Some(rebuild(elements, &last, sum))
+}
+
+// The subset with the largest sum no greater than the target.
+pub fn below<T: Arith>(elements: &[T], target: T) -> Option<Subset<T, u64>> {
+    check_len(elements);
+    if target < T::from(0) {
+        return None;
+    }
+    match dp_total(elements) {
+        Some(total) => dp_below(elements, total, target),
+        None => closest_below(Subset::all(elements).mask, elements, target),
+    }
+}
+
+// The subset with the smallest sum no less than the target: the complement of the largest one
+// no greater than what's left over.
+fn above<T: Arith>(elements: &[T], target: T) -> Option<Subset<T, u64>> {
+    let all = Subset::all(elements);
+    let rest = below(elements, all.sum - target.max(T::from(0)))?;
+    Some(Subset {
+        sum: all.sum - rest.sum,
+        mask: all.mask ^ rest.mask,
+    })
+}
+
+pub fn exact<T: Arith>(elements: &[T], target: T) -> Option<Subset<T, u64>> {
+    check_len(elements);
+    if target < T::from(0) {
+        return None;
+    }
+    match dp_total(elements) {
+        Some(total) => dp_below(elements, total, target).filter(|subset| subset.sum == target),
+        None => {
+            let all = Subset::all(elements).mask;
+            iterate_subsets_in_range(all, elements, target..target + T::from(1)).next()
+        }
+    }
+}
+
+// The subset with the sum closest to the target, preferring the one below on a tie.
+pub fn nearest<T: Arith>(elements: &[T], target: T) -> Subset<T, u64> {
+    match (below(elements, target), above(elements, target)) {
+        (Some(below), Some(above)) => {
+            if target - below.sum <= above.sum - target {
+                below
+            } else {
+                above
+            }
+        }
+        (Some(below), None) => below,
+        (None, Some(above)) => above,
+        (None, None) => unreachable!("every target is either above or below the empty subset"),
+    }
+}
+
+// How many subsets, counting each selection of indices separately, have a sum in the range.
+pub fn count_in_range<T: Arith>(elements: &[T], range: Range<T>) -> u64 {
+    let total = match dp_total(elements) {
+        Some(total) => total,
+        None => {
+            let all = Subset::all(elements).mask;
+            return count_subsets_in_range(all, elements, range);
+        }
+    };
+    let mut counts = vec![0_u64; total + 1];
+    counts[0] = 1;
+    for x in elements {
+        let x = x.to_usize().expect("elements must be non-negative");
+        for sum in (x..=total).rev() {
+            counts[sum] += counts[sum - x];
+        }
+    }
+    let start = range.start.max(T::from(0)).to_usize().unwrap_or(0);
+    let end = range.end.to_usize().unwrap_or(0).min(total + 1);
+    counts
+        .get(start..end)
+        .map_or(0, |counts| counts.iter().sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::subset::{all_subsets, Subset};
+    use crate::subset_sum::{below, count_in_range, exact, nearest};
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn assert_valid(subset: &Subset<i64, u64>, elements: &[i64]) {
+        assert_eq!(*subset, Subset::new(subset.mask, elements));
+    }
+    fn brute_below(elements: &[i64], target: i64) -> Option<i64> {
+        let subsets = all_subsets(elements).unwrap();
+        subsets
+            .iter()
+            .map(|subset| subset.sum)
+            .filter(|&sum| sum <= target)
+            .max()
+    }
+    // Small elements go through the dynamic program, large ones through meet in the middle.
+    fn check(elements: &[i64], target: i64) {
+        let subsets = all_subsets(elements).unwrap();
+        let closest = below(elements, target);
+        assert_eq!(
+            closest.as_ref().map(|subset| subset.sum),
+            brute_below(elements, target)
+        );
+        closest
+            .iter()
+            .for_each(|subset| assert_valid(subset, elements));
+        let hit = exact(elements, target);
+        assert_eq!(
+            hit.is_some(),
+            subsets.iter().any(|subset| subset.sum == target)
+        );
+        hit.iter().for_each(|subset| assert_valid(subset, elements));
+        let near = nearest(elements, target);
+        assert_valid(&near, elements);
+        let distance = subsets
+            .iter()
+            .map(|subset| (subset.sum - target).abs())
+            .min()
+            .unwrap();
+        assert_eq!((near.sum - target).abs(), distance);
+        let range = target / 2..target;
+        let count = subsets
+            .iter()
+            .filter(|subset| range.contains(&subset.sum))
+            .count();
+        assert_eq!(count_in_range(elements, range), count as u64);
+    }
+    proptest! {
+        #[test]
+        fn prop_subset_sum_small(ref elements in vec(0_i64..100, 0..10), target in -10_i64..500) {
+            check(elements, target);
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_subset_sum_large(ref elements in vec(10_000_i64..1_000_000, 1..10), target in 0_i64..5_000_000) {
+            check(elements, target);
+        }
+    }
+    #[test]
+    fn unit_reconcile() {
+        let invoices = [1250, 3400, 980, 2210, 760];
+        let payment = exact(&invoices, 2230).unwrap();
+        assert_eq!(payment.to_vec(&invoices), vec![1250, 980]);
+        assert_eq!(exact(&invoices, 2231), None);
+        assert_eq!(nearest(&invoices, 2231).sum, 2230);
+        assert_eq!(below(&invoices, 700), Some(Subset::empty()));
+        assert_eq!(count_in_range(&invoices, 0..1000), 3);
+    }
+    #[test]
+    fn unit_64_elements() {
+        let elements = [1_i64; 64];
+        assert_eq!(exact(&elements, 64).unwrap().mask, u64::MAX);
+        assert_eq!(below(&elements, 100).unwrap().mask, u64::MAX);
+    }
+    #[test]
+    #[should_panic(expected = "masks hold at most 64 elements")]
+    fn unit_too_many_elements() {
+        exact(&[1_i64; 65], 3);
+    }
+}
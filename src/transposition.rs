@@ -0,0 +1,126 @@
+use crate::arith::Arith;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+// A fixed-size table of search states already explored, so that a search reaching the same
+// state by another order can skip it. States are a depth plus a canonical key, such as the
+// sorted bin sums. Each state hashes to one slot and replaces whatever was there, so memory
+// stays bounded and recent states win. Full keys are stored, so a hit is never a false match.
+
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    depth: usize,
+    key: Box<[T]>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub probes: u64,
+    pub hits: u64,
+    // Stores that overwrote a different state.
+    pub replaced: u64,
+}
+
+impl Stats {
+    pub fn hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.probes as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Table<T> {
+    memory: usize,
+    slots: Vec<Option<Entry<T>>>,
+    stats: Stats,
+}
+
+impl<T: Arith> Table<T> {
+    // Roughly bounds the table to this many bytes. The slots are allocated on first use, once
+    // the key length is known.
+    pub fn with_memory(bytes: usize) -> Self {
+        Self {
+            memory: bytes,
+            slots: Vec::new(),
+            stats: Stats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    // Forgets the stored states, which only hold for the instance being searched, but keeps the
+    // statistics.
+    pub(crate) fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+
+    // How many states the table can hold, or 0 if it hasn't been used yet.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, depth: usize, key: &[T]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        depth.hash(&mut hasher);
+        key.hash(&mut hasher);
+        (hasher.finish() % self.slots.len() as u64) as usize
+    }
+
+    // Whether the state has been stored and not since replaced. If not, it's stored now.
+    pub(crate) fn visit(&mut self, depth: usize, key: &[T]) -> bool {
+        if self.slots.is_empty() {
+            let entry_size = mem::size_of::<Option<Entry<T>>>() + mem::size_of_val(key);
+            self.slots = vec![None; (self.memory / entry_size).max(1)];
+        }
+        self.stats.probes += 1;
+        let slot = self.slot(depth, key);
+        match self.slots[slot] {
+            Some(ref entry) if entry.depth == depth && *entry.key == *key => {
+                self.stats.hits += 1;
+                return true;
+            }
+            Some(_) => self.stats.replaced += 1,
+            None => {}
+        }
+        self.slots[slot] = Some(Entry {
+            depth,
+            key: key.into(),
+        });
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transposition::Table;
+
+    #[test]
+    fn unit_table() {
+        let mut table = Table::with_memory(1 << 12);
+        assert_eq!(table.capacity(), 0);
+        assert!(!table.visit(0, &[3, 2, 1]));
+        assert!(table.capacity() > 1);
+        assert!(table.visit(0, &[3, 2, 1]));
+        assert!(!table.visit(1, &[3, 2, 1]));
+        assert!(!table.visit(0, &[3, 2, 2]));
+        let stats = table.stats();
+        assert_eq!((stats.probes, stats.hits), (4, 1));
+        assert_eq!(stats.hit_rate(), 0.25);
+    }
+    #[test]
+    fn unit_table_bounded() {
+        // One slot: every new state evicts the last.
+        let mut table = Table::with_memory(1);
+        assert!(!table.visit(0, &[1]));
+        assert!(!table.visit(0, &[2]));
+        assert!(!table.visit(0, &[1]));
+        assert_eq!(table.capacity(), 1);
+        assert_eq!(table.stats().replaced, 2);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::arith::Arith;
+use crate::bounds;
+use crate::stats;
+use crate::subset::Subset;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+// Partitioning elements that have a weight in each of several dimensions, such as CPU, memory
+// and IO, so that the largest sum over every bin and dimension is as small as possible. A bin
+// is a Subset whose sum is the vector of its per-dimension sums.
+
+fn dimensions<T: Arith, V: AsRef<[T]>>(elements: &[V]) -> usize {
+    let d = elements.first().map_or(0, |x| x.as_ref().len());
+    assert!(
+        elements.iter().all(|x| x.as_ref().len() == d),
+        "elements have different numbers of dimensions"
+    );
+    d
+}
+
+// Bins are masks, so there can be at most 64 elements, and there has to be at least one bin.
+fn check<V>(elements: &[V], n: u8) {
+    assert!(n > 0, "can't partition into 0 bins");
+    assert!(elements.len() <= 64, "masks hold at most 64 elements");
+}
+
+fn add<T: Arith>(sums: &mut [T], x: &[T]) {
+    for (sum, &y) in sums.iter_mut().zip(x) {
+        *sum += y;
+    }
+}
+
+fn largest<T: Arith>(sums: &[T]) -> T {
+    sums.iter().cloned().max().unwrap_or_else(|| T::from(0))
+}
+
+fn total<T: Arith>(sums: &[T]) -> T {
+    sums.iter().cloned().sum()
+}
+
+pub fn subset<T: Arith, V: AsRef<[T]>>(mask: u64, elements: &[V]) -> Subset<Vec<T>, u64> {
+    let mut sum = vec![T::from(0); dimensions(elements)];
+    for (i, x) in elements.iter().enumerate() {
+        if mask & (1 << i) > 0 {
+            add(&mut sum, x.as_ref());
+        }
+    }
+    Subset { sum, mask }
+}
+
+// The largest sum over all bins and dimensions.
+pub fn score<T: Arith>(partitions: &[Subset<Vec<T>, u64>]) -> T {
+    partitions
+        .iter()
+        .map(|subset| largest(&subset.sum))
+        .max()
+        .expect("partitions is empty")
+}
+
+// The largest difference between two bins in any one dimension.
+fn spread<T: Arith>(bins: &[Subset<Vec<T>, u64>]) -> T {
+    let d = bins[0].sum.len();
+    (0..d)
+        .map(|j| {
+            let max = bins.iter().map(|bin| bin.sum[j]).max().unwrap();
+            let min = bins.iter().map(|bin| bin.sum[j]).min().unwrap();
+            max - min
+        })
+        .max()
+        .unwrap_or_else(|| T::from(0))
+}
+
+fn sort_descending<T: Arith>(partitions: &mut [Subset<Vec<T>, u64>]) {
+    partitions.sort_by_key(|subset| (Reverse(largest(&subset.sum)), Reverse(total(&subset.sum))));
+}
+
+struct Node<T> {
+    bins: Vec<Subset<Vec<T>, u64>>,
+    spread: T,
+}
+
+impl<T: Arith> PartialEq for Node<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.spread == other.spread
+    }
+}
+impl<T: Arith> Eq for Node<T> {}
+impl<T: Arith> PartialOrd for Node<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T: Arith> Ord for Node<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.spread.cmp(&other.spread)
+    }
+}
+
+// Vector Karmarkar-Karp: the partial partitionings with the largest spread are merged first,
+// pairing the heaviest bins of one with the lightest of the other, where bins are ordered by
+// their total over all dimensions.
+pub fn kk<T: Arith, V: AsRef<[T]>>(elements: &[V], n: u8) -> Vec<Subset<Vec<T>, u64>> {
+    check(elements, n);
+    let d = dimensions(elements);
+    let empty = Subset {
+        sum: vec![T::from(0); d],
+        mask: 0,
+    };
+    let mut heap: BinaryHeap<Node<T>> = (0..elements.len())
+        .map(|i| {
+            let mut bins = vec![empty.clone(); n as usize];
+            bins[0] = subset(1 << i, elements);
+            let spread = spread(&bins);
+            Node { bins, spread }
+        })
+        .collect();
+    let mut result = match heap.pop() {
+        Some(node) => node,
+        None => return vec![empty; n as usize],
+    };
+    while let Some(mut other) = heap.pop() {
+        result.bins.sort_by_key(|bin| Reverse(total(&bin.sum)));
+        other.bins.sort_by_key(|bin| total(&bin.sum));
+        for (bin, light) in result.bins.iter_mut().zip(other.bins) {
+            add(&mut bin.sum, &light.sum);
+            bin.mask |= light.mask;
+        }
+        result.spread = spread(&result.bins);
+        heap.push(result);
+        result = heap.pop().expect("heap is empty");
+    }
+    sort_descending(&mut result.bins);
+    result.bins
+}
+
+struct Search<'a, T, V> {
+    elements: &'a [V],
+    order: Vec<usize>,
+    best: Vec<Subset<Vec<T>, u64>>,
+    best_score: T,
+    // No partitioning can do better than this, so the search stops if it's reached.
+    lower_bound: T,
+}
+
+impl<'a, T: Arith, V: AsRef<[T]>> Search<'a, T, V> {
+    fn expand(&mut self, depth: usize, bins: &mut [Subset<Vec<T>, u64>]) {
+        stats::count_node();
+        let current = score(bins);
+        if current >= self.best_score {
+            return;
+        }
+        if depth == self.order.len() {
+            self.best_score = current;
+            self.best = bins.to_vec();
+            return;
+        }
+        let i = self.order[depth];
+        let x = self.elements[i].as_ref();
+        let mut candidates: Vec<usize> = (0..bins.len()).collect();
+        candidates.sort_by_key(|&bin| {
+            let mut sum = bins[bin].sum.clone();
+            add(&mut sum, x);
+            (largest(&sum), total(&sum))
+        });
+        let mut tried: Vec<Vec<T>> = Vec::with_capacity(bins.len());
+        for bin in candidates {
+            // Bins with the same sums lead to the same subtrees.
+            if tried.contains(&bins[bin].sum) {
+                continue;
+            }
+            tried.push(bins[bin].sum.clone());
+            let saved = bins[bin].clone();
+            add(&mut bins[bin].sum, x);
+            bins[bin].mask |= 1 << i;
+            self.expand(depth + 1, bins);
+            bins[bin] = saved;
+            if self.best_score <= self.lower_bound {
+                return;
+            }
+        }
+    }
+}
+
+// The largest of the scalar lower bounds on each dimension taken alone.
+pub fn lower_bound<T: Arith, V: AsRef<[T]>>(elements: &[V], n: u8) -> T {
+    assert!(n > 0, "can't partition into 0 bins");
+    (0..dimensions(elements))
+        .map(|j| {
+            let column: Vec<T> = elements.iter().map(|x| x.as_ref()[j]).collect();
+            bounds::lower_bound(&column, n)
+        })
+        .max()
+        .unwrap_or_else(|| T::from(0))
+}
+
+// Exact search in the style of gcc: elements largest first, each tried in every bin that
+// doesn't give the same sums as one already tried, pruning on the best score found, which
+// starts from vector KK.
+pub fn gcc<T: Arith, V: AsRef<[T]>>(elements: &[V], n: u8) -> Vec<Subset<Vec<T>, u64>> {
+    check(elements, n);
+    let d = dimensions(elements);
+    let best = kk(elements, n);
+    let mut order: Vec<usize> = (0..elements.len()).collect();
+    order.sort_by_key(|&i| {
+        let x = elements[i].as_ref();
+        (Reverse(largest(x)), Reverse(total(x)))
+    });
+    let mut search = Search {
+        elements,
+        order,
+        best_score: score(&best),
+        best,
+        lower_bound: lower_bound(elements, n),
+    };
+    let mut bins = vec![
+        Subset {
+            sum: vec![T::from(0); d],
+            mask: 0,
+        };
+        n as usize
+    ];
+    search.expand(0, &mut bins);
+    sort_descending(&mut search.best);
+    search.best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gcc::find_best_partitioning;
+    use crate::subset::Subset;
+    use crate::vector::{gcc, kk, lower_bound, score, subset};
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    fn assert_partitions(partitions: &[Subset<Vec<i32>, u64>], elements: &[[i32; 3]], n: u8) {
+        assert_eq!(partitions.len(), n as usize);
+        let mut union = 0;
+        for bin in partitions {
+            assert_eq!(union & bin.mask, 0);
+            assert_eq!(*bin, subset(bin.mask, elements));
+            union |= bin.mask;
+        }
+        assert_eq!(union, (1 << elements.len()) - 1);
+    }
+
+    // Every assignment of elements to bins.
+    fn brute(elements: &[[i32; 3]], n: u8) -> i32 {
+        let n = n as usize;
+        (0..n.pow(elements.len() as u32))
+            .map(|mut code| {
+                let mut masks = vec![0_u64; n];
+                for i in 0..elements.len() {
+                    masks[code % n] |= 1 << i;
+                    code /= n;
+                }
+                let partitions: Vec<_> = masks.iter().map(|&mask| subset(mask, elements)).collect();
+                score(&partitions)
+            })
+            .min()
+            .unwrap()
+    }
+    proptest! {
+        #[test]
+        fn prop_vector_gcc_brute(ref elements in vec([1_i32..100, 1_i32..100, 1_i32..100], 1..8), n in 2_u8..4) {
+            let exact = gcc(elements, n);
+            assert_partitions(&exact, elements, n);
+            let heuristic = kk(elements, n);
+            assert_partitions(&heuristic, elements, n);
+            assert_eq!(score(&exact), brute(elements, n));
+            assert!(score(&exact) <= score(&heuristic));
+            assert!(score(&exact) >= lower_bound(elements, n));
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_vector_one_dimension(ref elements in vec(1_i32..1000, 1..10)) {
+            let vectors: Vec<Vec<i32>> = elements.iter().map(|&x| vec![x]).collect();
+            let (_, best) = find_best_partitioning(elements, 3);
+            assert_eq!(score(&gcc(&vectors, 3)), best);
+        }
+    }
+    #[test]
+    fn unit_vector_balanced() {
+        // CPU-heavy and memory-heavy jobs have to be paired up to balance both.
+        let elements = [[8, 1], [7, 2], [1, 8], [2, 7]];
+        let partitions = gcc(&elements, 2);
+        assert_eq!(score(&partitions), 9);
+        assert_eq!(partitions[0].sum, vec![9, 9]);
+        assert_eq!(partitions[1].sum, vec![9, 9]);
+        assert_eq!(lower_bound(&elements, 2), 9);
+        assert!(score(&kk(&elements, 2)) >= 9);
+    }
+    #[test]
+    #[should_panic(expected = "can't partition into 0 bins")]
+    fn unit_vector_no_bins() {
+        gcc(&[[1, 2]], 0);
+    }
+    #[test]
+    #[should_panic(expected = "can't partition into 0 bins")]
+    fn unit_vector_kk_no_bins() {
+        kk(&[[1, 2]], 0);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::arith::Arith;
+use crate::bounds;
+use crate::gcc;
+use crate::subset::Subset;
+use std::fmt;
+
+// Checks claimed partitionings, whether they came from this crate, an older run or another
+// tool, against the elements they're supposed to partition.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Invalid<T> {
+    WrongCount { expected: usize, found: usize },
+    UnknownElement { bin: usize, element: usize },
+    Overlap { element: usize },
+    Missing { element: usize },
+    WrongSum { bin: usize, claimed: T, actual: T },
+}
+
+impl<T: Arith> fmt::Display for Invalid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Invalid::WrongCount { expected, found } => {
+                write!(f, "expected {} bins, found {}", expected, found)
+            }
+            Invalid::UnknownElement { bin, element } => {
+                write!(
+                    f,
+                    "bin {} contains element {}, which doesn't exist",
+                    bin, element
+                )
+            }
+            Invalid::Overlap { element } => {
+                write!(f, "element {} is in more than one bin", element)
+            }
+            Invalid::Missing { element } => write!(f, "element {} is in no bin", element),
+            Invalid::WrongSum {
+                bin,
+                claimed,
+                actual,
+            } => write!(
+                f,
+                "bin {} claims sum {} but sums to {}",
+                bin, claimed, actual
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Certificate {
+    // The score meets the lower bound, which no partitioning can beat.
+    LowerBound,
+    // An exhaustive search found nothing with a lower score.
+    Exhaustive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report<T> {
+    pub score: T,
+    pub lower_bound: T,
+    // How far the score is above the lower bound. Zero means the partitioning is perfect.
+    pub gap: T,
+    pub certificate: Option<Certificate>,
+}
+
+pub fn verify<T: Arith>(
+    elements: &[T],
+    n: u8,
+    partitions: &[Subset<T, u64>],
+) -> Result<Report<T>, Invalid<T>> {
+    if partitions.len() != n as usize {
+        return Err(Invalid::WrongCount {
+            expected: n as usize,
+            found: partitions.len(),
+        });
+    }
+    let all = Subset::all(elements).mask;
+    let mut seen = 0_u64;
+    for (bin, subset) in partitions.iter().enumerate() {
+        let unknown = subset.mask & !all;
+        if unknown != 0 {
+            return Err(Invalid::UnknownElement {
+                bin,
+                element: unknown.trailing_zeros() as usize,
+            });
+        }
+        let overlap = subset.mask & seen;
+        if overlap != 0 {
+            return Err(Invalid::Overlap {
+                element: overlap.trailing_zeros() as usize,
+            });
+        }
+        seen |= subset.mask;
+        let actual = Subset::new(subset.mask, elements).sum;
+        if actual != subset.sum {
+            return Err(Invalid::WrongSum {
+                bin,
+                claimed: subset.sum,
+                actual,
+            });
+        }
+    }
+    let missing = all & !seen;
+    if missing != 0 {
+        return Err(Invalid::Missing {
+            element: missing.trailing_zeros() as usize,
+        });
+    }
+    let score = partitions
+        .iter()
+        .map(|subset| subset.sum)
+        .max()
+        .expect("partitions is empty");
+    let lower_bound = bounds::lower_bound(elements, n);
+    let gap = score - lower_bound;
+    let certificate = if score == lower_bound {
+        Some(Certificate::LowerBound)
+    } else {
+        None
+    };
+    Ok(Report {
+        score,
+        lower_bound,
+        gap,
+        certificate,
+    })
+}
+
+// Like verify, but when the lower bound isn't met, runs an exact search to decide whether the
+// score is optimal anyway. That search is exponential in the number of elements.
+pub fn certify<T: Arith>(
+    elements: &[T],
+    n: u8,
+    partitions: &[Subset<T, u64>],
+) -> Result<Report<T>, Invalid<T>> {
+    let mut report = verify(elements, n, partitions)?;
+    if report.certificate.is_none() {
+        let (_, optimal) = gcc::find_best_partitioning(elements, n);
+        if optimal == report.score {
+            report.certificate = Some(Certificate::Exhaustive);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::select::{partition_using, PartitionMethod};
+    use crate::subset::Subset;
+    use crate::verify::{certify, verify, Certificate, Invalid};
+    use proptest::collection::vec;
+    use proptest::proptest;
+
+    proptest! {
+        #[test]
+        fn prop_verify_kk(ref elements in vec(1_i32..1000, 1..12), n in 2_u8..5) {
+            let partitions = partition_using(PartitionMethod::KK, elements, n).to_subsets(elements);
+            let report = verify(elements, n, &partitions).unwrap();
+            assert_eq!(report.score, partitions[0].sum);
+            assert!(report.score >= report.lower_bound);
+        }
+    }
+    proptest! {
+        #[test]
+        fn prop_certify_gcc(ref elements in vec(1_i32..1000, 1..10), n in 2_u8..5) {
+            let partitions = partition_using(PartitionMethod::GCC, elements, n).to_subsets(elements);
+            let report = certify(elements, n, &partitions).unwrap();
+            assert!(report.certificate.is_some());
+        }
+    }
+    #[test]
+    fn unit_verify_perfect() {
+        let elements = [5, 4, 3, 3, 3];
+        let partitions = vec![
+            Subset::new(0b00011, &elements),
+            Subset::new(0b11100, &elements),
+        ];
+        let report = verify(&elements, 2, &partitions).unwrap();
+        assert_eq!(report.score, 9);
+        assert_eq!(report.gap, 0);
+        assert_eq!(report.certificate, Some(Certificate::LowerBound));
+    }
+    #[test]
+    fn unit_certify_exhaustive() {
+        // Every sum is even, so the bound of 7 can't be met.
+        let elements = [4, 4, 2, 2, 2];
+        let partitions = vec![
+            Subset::new(0b00011, &elements),
+            Subset::new(0b11100, &elements),
+        ];
+        let report = verify(&elements, 2, &partitions).unwrap();
+        assert_eq!(report.certificate, None);
+        let report = certify(&elements, 2, &partitions).unwrap();
+        assert_eq!(report.gap, 1);
+        assert_eq!(report.certificate, Some(Certificate::Exhaustive));
+    }
+    #[test]
+    fn unit_verify_pigeonhole() {
+        let elements = [5, 5, 5];
+        let partitions = vec![Subset::new(0b011, &elements), Subset::new(0b100, &elements)];
+        let report = verify(&elements, 2, &partitions).unwrap();
+        assert_eq!(report.certificate, Some(Certificate::LowerBound));
+    }
+    #[test]
+    fn unit_certify_suboptimal() {
+        let elements = [4, 3, 3, 2];
+        let partitions = vec![
+            Subset::new(0b0011, &elements),
+            Subset::new(0b1100, &elements),
+        ];
+        let report = certify(&elements, 2, &partitions).unwrap();
+        assert_eq!(report.certificate, None);
+    }
+    #[test]
+    fn unit_verify_64() {
+        let elements: Vec<i32> = (1..=64).collect();
+        let partitions = vec![
+            Subset::new(0x5555_5555_5555_5555, &elements),
+            Subset::new(0xAAAA_AAAA_AAAA_AAAA, &elements),
+        ];
+        let report = verify(&elements, 2, &partitions).unwrap();
+        assert_eq!(report.score, 1056);
+        let missing = vec![partitions[0].clone(), Subset::empty()];
+        assert_eq!(
+            verify(&elements, 2, &missing),
+            Err(Invalid::Missing { element: 1 })
+        );
+    }
+    #[test]
+    fn unit_verify_errors() {
+        let elements = [5, 4, 3];
+        let overlapping = vec![Subset::new(0b011, &elements), Subset::new(0b110, &elements)];
+        assert_eq!(
+            verify(&elements, 2, &overlapping),
+            Err(Invalid::Overlap { element: 1 })
+        );
+        let missing = vec![Subset::new(0b001, &elements), Subset::new(0b010, &elements)];
+        assert_eq!(
+            verify(&elements, 2, &missing),
+            Err(Invalid::Missing { element: 2 })
+        );
+        let wrong_sum = vec![
+            Subset {
+                sum: 8,
+                mask: 0b011,
+            },
+            Subset::new(0b100, &elements),
+        ];
+        assert_eq!(
+            verify(&elements, 2, &wrong_sum),
+            Err(Invalid::WrongSum {
+                bin: 0,
+                claimed: 8,
+                actual: 9
+            })
+        );
+        let unknown = vec![
+            Subset::new(0b111, &elements),
+            Subset {
+                sum: 0,
+                mask: 0b1000,
+            },
+        ];
+        assert_eq!(
+            verify(&elements, 2, &unknown),
+            Err(Invalid::UnknownElement { bin: 1, element: 3 })
+        );
+        assert_eq!(
+            verify(&elements, 3, &unknown),
+            Err(Invalid::WrongCount {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+}